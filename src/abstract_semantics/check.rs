@@ -0,0 +1,281 @@
+use crate::abstract_semantics::state::*;
+use crate::domain::domain::*;
+use crate::domain::lattice::*;
+use crate::parser::ast::*;
+use crate::parser::cfg::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+// something that may go wrong when running the program, as found by the analysis
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+}
+
+// the alarms of a program, out of the number of run-time checks made on it: the
+// checks without an alarm are proven to never fail
+#[derive(Debug, Clone)]
+pub struct Checks {
+    pub alarms: Vec<Alarm>,
+    pub count: usize,
+}
+
+struct Checker {
+    alarms: Vec<Alarm>,
+    checked: Vec<(Span, &'static str)>,
+}
+
+// checks every program point against the state reaching it
+pub fn check<T: Domain>(cfg: &Cfg, invariants: &[State<T>]) -> Checks {
+    let mut checker = Checker {
+        alarms: vec![],
+        checked: vec![],
+    };
+
+    for (i, point) in cfg.points.iter().enumerate() {
+        let preds = cfg.predecessors(Node::Point(i));
+        let state = state_before(cfg, invariants, i);
+
+        checker.point(point, &state);
+
+        // the only predecessor of the exit of a loop is its head
+        let is_exit = matches!(
+            point,
+            ProgramPoint::EndWhile(_) | ProgramPoint::EndRepeat(_) | ProgramPoint::EndFor(_)
+        );
+        if let (true, [Node::Point(head)]) = (is_exit, &preds[..]) {
+            checker.checked("non-terminating-loop", cfg.points[*head].span());
+            if state != State::Bottom && invariants[i] == State::Bottom {
+                checker.alarm(
+                    "non-terminating-loop",
+                    Severity::Warning,
+                    "the loop never terminates".to_string(),
+                    cfg.points[*head].span(),
+                );
+            }
+        }
+    }
+
+    // the body of a repeat is checked twice
+    checker
+        .checked
+        .sort_by_key(|(span, rule)| (span.start, span.end, *rule));
+    checker
+        .checked
+        .dedup_by_key(|(span, rule)| (span.start, span.end, *rule));
+
+    Checks {
+        alarms: dedup(checker.alarms),
+        count: checker.checked.len(),
+    }
+}
+
+// the state reaching the i-th program point, from the states after its predecessors
+pub fn state_before<T: Domain>(cfg: &Cfg, invariants: &[State<T>], i: usize) -> State<T> {
+    cfg.predecessors(Node::Point(i))
+        .iter()
+        .map(|node| match node {
+            Node::Point(j) => invariants[*j].clone(),
+            _ => State::new(),
+        })
+        .fold(State::Bottom, |acc, s| acc.lub(&s))
+}
+
+// keeps the worst alarm of each rule at each location, sorted by location
+pub fn dedup(mut alarms: Vec<Alarm>) -> Vec<Alarm> {
+    alarms.sort_by_key(|a| {
+        (
+            a.span.start,
+            a.span.end,
+            a.rule,
+            std::cmp::Reverse(a.severity),
+        )
+    });
+    alarms.dedup_by_key(|a| (a.span.start, a.span.end, a.rule));
+    alarms
+}
+
+impl Checker {
+    fn point<T: Domain>(&mut self, point: &ProgramPoint, state: &State<T>) {
+        match point {
+            ProgramPoint::Assignment(stmt) => match &stmt.node {
+                Statement::Assignment { val, .. } => {
+                    self.aexpr(val, state);
+                }
+                Statement::CompoundAssignment { var, op, val } => {
+                    self.variable(var, state, stmt.span);
+                    self.aexpr(val, state);
+                    if *op == ArithmeticOp::Div {
+                        self.divisor(val, state, stmt.span);
+                    }
+                }
+                Statement::Increment(var) | Statement::Decrement(var) => {
+                    self.variable(var, state, stmt.span)
+                }
+                _ => (),
+            },
+
+            ProgramPoint::IfGuard(cond)
+            | ProgramPoint::WhileGuard(cond)
+            | ProgramPoint::RepeatGuard(cond)
+            | ProgramPoint::ForGuard(cond) => self.bexpr(cond, state),
+
+            ProgramPoint::ForInit { from, .. } => {
+                self.aexpr(from, state);
+            }
+
+            _ => (),
+        }
+    }
+
+    // checks the subexpressions in evaluation order, returns the state after `expr`
+    fn aexpr<T: Domain>(&mut self, expr: &Spanned<ArithmeticExpr>, state: &State<T>) -> State<T> {
+        match &expr.node {
+            ArithmeticExpr::Variable(var)
+            | ArithmeticExpr::PostIncrement(var)
+            | ArithmeticExpr::PostDecrement(var) => self.variable(var, state, expr.span),
+
+            ArithmeticExpr::Div(a1, a2) => {
+                let new_state = self.aexpr(a1, state);
+                self.aexpr(a2, &new_state);
+                self.divisor(a2, &new_state, expr.span);
+            }
+
+            ArithmeticExpr::Interval(a1, a2) => {
+                let new_state = self.aexpr(a1, state);
+                self.aexpr(a2, &new_state);
+                self.bounds(a1, a2, state, expr.span);
+            }
+
+            ArithmeticExpr::Add(a1, a2)
+            | ArithmeticExpr::Sub(a1, a2)
+            | ArithmeticExpr::Mul(a1, a2) => {
+                let new_state = self.aexpr(a1, state);
+                self.aexpr(a2, &new_state);
+            }
+
+            ArithmeticExpr::Number(_) => (),
+        }
+        T::eval_aexpr(expr, state).1
+    }
+
+    fn bexpr<T: Domain>(&mut self, expr: &Spanned<BooleanExpr>, state: &State<T>) {
+        match &expr.node {
+            BooleanExpr::True | BooleanExpr::False => (),
+            BooleanExpr::Not(b) => self.bexpr(b, state),
+
+            // without strict evaluation the right operand only runs when needed
+            BooleanExpr::And(b1, b2) | BooleanExpr::Or(b1, b2) => {
+                self.bexpr(b1, state);
+                let rhs_state = match (STRICT_EVALUATION.get(), &expr.node) {
+                    (true, _) => state.clone(),
+                    (_, BooleanExpr::And(..)) => T::eval_bexpr(b1, state),
+                    _ => T::eval_bexpr(&b1.negate(), state),
+                };
+                self.bexpr(b2, &rhs_state);
+            }
+
+            BooleanExpr::NumEq(a1, a2)
+            | BooleanExpr::NumNotEq(a1, a2)
+            | BooleanExpr::NumLt(a1, a2)
+            | BooleanExpr::NumGt(a1, a2)
+            | BooleanExpr::NumLtEq(a1, a2)
+            | BooleanExpr::NumGtEq(a1, a2) => {
+                let new_state = self.aexpr(a1, state);
+                self.aexpr(a2, &new_state);
+            }
+        }
+    }
+
+    // a variable is undefined when no path reaching `state` assigns it
+    fn variable<T: Domain>(&mut self, var: &Identifier, state: &State<T>, span: Span) {
+        self.checked("undefined-variable", span);
+        if let State::Just(vars) = state {
+            if !vars.contains_key(var) {
+                let message = format!("variable `{}` is used before being assigned", var);
+                self.alarm("undefined-variable", Severity::Error, message, span);
+            }
+        }
+    }
+
+    // flags the division spanning `span` if `divisor`, run in `state`, may be zero
+    fn divisor<T: Domain>(
+        &mut self,
+        divisor: &Spanned<ArithmeticExpr>,
+        state: &State<T>,
+        span: Span,
+    ) {
+        self.checked("division-by-zero", span);
+        let value = T::eval_aexpr(divisor, state).0;
+
+        let (severity, message) = match value {
+            v if v == T::ZERO => (Severity::Error, "division by zero"),
+            v if !v.glb(&T::ZERO).is_bottom() => (Severity::Warning, "possible division by zero"),
+            _ => return,
+        };
+        self.alarm("division-by-zero", severity, message.to_string(), span);
+    }
+
+    // flags the interval `[a1, a2]` if its lower bound may be greater than its upper one
+    fn bounds<T: Domain>(
+        &mut self,
+        a1: &Spanned<ArithmeticExpr>,
+        a2: &Spanned<ArithmeticExpr>,
+        state: &State<T>,
+        span: Span,
+    ) {
+        self.checked("invalid-interval-bounds", span);
+        let valid = T::eval_bexpr(
+            &BooleanExpr::NumLtEq(Box::new(a1.clone()), Box::new(a2.clone())),
+            state,
+        );
+        let invalid = T::eval_bexpr(
+            &BooleanExpr::NumGt(Box::new(a1.clone()), Box::new(a2.clone())),
+            state,
+        );
+
+        let (severity, message) = match (valid, invalid) {
+            (_, State::Bottom) => return,
+            (State::Bottom, _) => (Severity::Error, "invalid interval bounds"),
+            _ => (Severity::Warning, "possibly invalid interval bounds"),
+        };
+        self.alarm(
+            "invalid-interval-bounds",
+            severity,
+            message.to_string(),
+            span,
+        );
+    }
+
+    fn checked(&mut self, rule: &'static str, span: Span) {
+        self.checked.push((span, rule));
+    }
+
+    fn alarm(&mut self, rule: &'static str, severity: Severity, message: String, span: Span) {
+        self.alarms.push(Alarm {
+            rule,
+            severity,
+            message,
+            span,
+        });
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
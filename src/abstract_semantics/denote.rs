@@ -3,6 +3,18 @@ use crate::abstract_semantics::state::*;
 use crate::domain::domain::*;
 use crate::domain::lattice::*;
 use crate::parser::ast::*;
+use std::cell::RefCell;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+thread_local! {
+    // when set, the loops give up on their fixpoint as soon as the flag is
+    pub static CANCEL: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+// the payload of the unwinding of a cancelled analysis
+pub struct Cancelled;
 
 // --- type aliases
 
@@ -15,32 +27,30 @@ pub fn denote_stmt<'a, T: Domain + 'a>(stmt: Statement) -> StateFunction<'a, T>
     match stmt.clone() {
         Statement::Skip => id(),
 
-        Statement::Chain(s1, s2) => compose(denote_stmt(*s1), denote_stmt(*s2)),
+        // an unparsable statement has no program point and leaves the state untouched
+        Statement::Error(_) => Box::new(|state| (state, vec![])),
+
+        Statement::Chain(s1, s2) => compose(denote_stmt(s1.node), denote_stmt(s2.node)),
 
-        Statement::Assignment { var, val } => state_update(var, *val),
+        Statement::Assignment { var, val } => state_update(var, val.node),
 
-        Statement::If { cond, s1, s2 } => conditional(*cond, denote_stmt(*s1), denote_stmt(*s2)),
+        Statement::If { cond, s1, s2 } => {
+            conditional(cond.node, denote_stmt(s1.node), denote_stmt(s2.node))
+        }
 
         Statement::While { cond, body, .. } => {
-            let body = denote_stmt(*body);
+            let body = denote_stmt(body.node);
 
             Box::new(move |state| {
                 let f: LoopIteration<T> = Box::new(|prev_state: &State<T>| {
-                    let cond_state = T::eval_bexpr(&cond, &prev_state);
+                    let cond_state = T::eval_bexpr(&cond, prev_state);
                     state.lub(&body(cond_state).0)
                 });
                 while_semantic(f, &cond, &body, get_loop_delay(&stmt))
             })
         }
 
-        Statement::RepeatUntil { body, cond, delay } => denote_stmt(Statement::Chain(
-            body.clone(),
-            Box::new(Statement::While {
-                cond: Box::new(cond.negate()),
-                body: Box::new(*body),
-                delay: delay,
-            }),
-        )),
+        _ => unreachable!("[ERROR] statement was not lowered: {}", stmt),
     }
 }
 
@@ -99,10 +109,10 @@ fn while_semantic<T: Domain>(
     body: &StateFunction<T>,
     delay: i64,
 ) -> (State<T>, Invariant<T>) {
-    let loop_inv = fix(&f, |prev, curr| prev.widen(&curr), State::Bottom, delay);
-    let loop_inv = fix(&f, |prev, curr| prev.narrow(&curr), loop_inv, 0);
+    let loop_inv = fix(&f, |prev, curr| prev.widen(curr), State::Bottom, delay);
+    let loop_inv = fix(&f, |prev, curr| prev.narrow(curr), loop_inv, 0);
 
-    let cond_state = T::eval_bexpr(&cond, &loop_inv);
+    let cond_state = T::eval_bexpr(cond, &loop_inv);
     let body_state = body(cond_state.clone()).1;
     let exit_state = T::eval_bexpr(&cond.negate(), &loop_inv);
 
@@ -124,6 +134,11 @@ fn fix<T: Domain>(
     mut delay: i64,
 ) -> State<T> {
     loop {
+        if CANCEL.with_borrow(|c| c.as_ref().is_some_and(|c| c.load(Ordering::Relaxed))) {
+            // no hook runs on resuming, the cancellation is not reported as a panic
+            panic::resume_unwind(Box::new(Cancelled));
+        }
+
         let mut curr_state = f(&prev_state);
 
         match delay == 0 {
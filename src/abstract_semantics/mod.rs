@@ -1,3 +1,4 @@
+pub mod check;
 pub mod denote;
 pub mod invariant;
 pub mod state;
@@ -25,7 +25,7 @@ impl<T: Domain> State<T> {
     pub fn put(&self, var: &Identifier, val: T) -> Self {
         match self.clone() {
             State::Bottom => State::Bottom,
-            _ if val == T::BOT => State::Bottom,
+            _ if val.is_bottom() => State::Bottom,
             State::Just(mut s) => {
                 s.insert(var.to_string(), val);
                 State::Just(s)
@@ -34,24 +34,41 @@ impl<T: Domain> State<T> {
     }
 }
 
+impl<T: Domain> Default for State<T> {
+    fn default() -> Self {
+        State::new()
+    }
+}
+
 impl<T: Domain> Lattice for State<T> {
     const TOP: Self = State::Bottom;
     const BOT: Self = State::Bottom;
     const UNIT: Self = State::Bottom;
     const ZERO: Self = State::Bottom;
 
+    // a variable missing from a state is unassigned, which is below any value
+    fn leq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (State::Bottom, _) => true,
+            (_, State::Bottom) => false,
+            (State::Just(s1), State::Just(s2)) => s1
+                .iter()
+                .all(|(var, v1)| s2.get(var).is_some_and(|v2| v1.leq(v2))),
+        }
+    }
+
     fn lub(&self, other: &Self) -> Self {
         match (self, other) {
             (State::Bottom, _) => other.clone(),
             (_, State::Bottom) => self.clone(),
-            (State::Just(s1), State::Just(s2)) => point_wise_op(s1, s2, |a, b| a.lub(&b)),
+            (State::Just(s1), State::Just(s2)) => point_wise_union(s1, s2, |a, b| a.lub(&b)),
         }
     }
 
     fn glb(&self, other: &Self) -> Self {
         match (self, other) {
             (State::Bottom, _) | (_, State::Bottom) => State::Bottom,
-            (State::Just(s1), State::Just(s2)) => point_wise_op(s1, s2, |a, b| a.glb(&b)),
+            (State::Just(s1), State::Just(s2)) => point_wise_intersection(s1, s2, |a, b| a.glb(&b)),
         }
     }
 
@@ -59,17 +76,24 @@ impl<T: Domain> Lattice for State<T> {
         match (self, other) {
             (State::Bottom, _) => other.clone(),
             (_, State::Bottom) => self.clone(),
-            (State::Just(s1), State::Just(s2)) => point_wise_op(s1, s2, |a, b| a.widen(&b)),
+            (State::Just(s1), State::Just(s2)) => point_wise_union(s1, s2, |a, b| a.widen(&b)),
         }
     }
 
     fn narrow(&self, other: &Self) -> Self {
         match (self, other) {
             (State::Bottom, _) | (_, State::Bottom) => State::Bottom,
-            (State::Just(s1), State::Just(s2)) => point_wise_op(s1, s2, |a, b| a.narrow(&b)),
+            (State::Just(s1), State::Just(s2)) => {
+                point_wise_intersection(s1, s2, |a, b| a.narrow(&b))
+            }
         }
     }
 
+    // no state binds every variable, `TOP` only stands in for the constant
+    fn is_top(&self) -> bool {
+        false
+    }
+
     fn round(_: &Self) -> Self {
         unreachable!()
     }
@@ -80,20 +104,7 @@ impl<T: Domain> PartialEq for State<T> {
         match (self, other) {
             (State::Bottom, State::Bottom) => true,
             (State::Bottom, _) | (_, State::Bottom) => false,
-            (State::Just(s1), State::Just(s2)) => {
-                if s1.len() != s2.len() {
-                    return false;
-                }
-
-                for (k, v1) in s1.iter() {
-                    if let Some(v2) = s2.get(k) {
-                        if v1 != v2 {
-                            return false;
-                        }
-                    }
-                }
-                return true;
-            }
+            (State::Just(s1), State::Just(s2)) => s1 == s2,
         }
     }
 }
@@ -118,17 +129,34 @@ impl<T: Domain> fmt::Display for State<T> {
 
 // --- helpers
 
-fn point_wise_op<T: Domain>(
+// the variables of either state, those of a single one keeping their value
+fn point_wise_union<T: Domain>(
     s1: &HashMap<Identifier, T>,
     s2: &HashMap<Identifier, T>,
     op: fn(T, T) -> T,
 ) -> State<T> {
     let mut new_state = State::new();
-    for (var1, val1) in s1 {
-        match s2.get(var1) {
-            Some(val2) => new_state = new_state.put(&var1, op(val1.clone(), val2.clone())),
-            None => new_state = new_state.put(&var1, val1.clone()),
-        }
+    for (var, val) in s1.iter().chain(s2) {
+        let val = match (s1.get(var), s2.get(var)) {
+            (Some(val1), Some(val2)) => op(*val1, *val2),
+            _ => *val,
+        };
+        new_state = new_state.put(var, val);
     }
     new_state
 }
+
+// the variables of both states with a value in common, any other variable being
+// unassigned in one of them at least
+fn point_wise_intersection<T: Domain>(
+    s1: &HashMap<Identifier, T>,
+    s2: &HashMap<Identifier, T>,
+    op: fn(T, T) -> T,
+) -> State<T> {
+    let new_state = s1
+        .iter()
+        .filter_map(|(var, val1)| Some((var.clone(), op(*val1, *s2.get(var)?))))
+        .filter(|(_, val)| !val.is_bottom())
+        .collect();
+    State::Just(new_state)
+}
@@ -0,0 +1,185 @@
+use crate::abstract_semantics;
+use crate::abstract_semantics::check::*;
+use crate::concrete_semantics;
+use crate::domain::domain::*;
+use crate::domain::interval::*;
+use crate::parser::ast::*;
+use crate::parser::cfg::*;
+use crate::parser::lower::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+use crate::types::integer::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+// --- options
+
+// the configuration of a run or an analysis, in effect on the calling thread for
+// the time of the call only
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    // the bounds of the interval domain
+    pub bounds: (Integer, Integer),
+    // the delay of the loops with no `@delay` of their own, when set
+    pub widening_delay: Option<i64>,
+    // the values the interval domain widens to before the bounds
+    pub widening_thresholds: Vec<Integer>,
+    // `&&` and `||` evaluate both operands instead of short-circuiting
+    pub strict: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            bounds: (Integer::NegInf, Integer::PosInf),
+            widening_delay: None,
+            widening_thresholds: vec![],
+            strict: false,
+        }
+    }
+}
+
+impl Options {
+    // the configuration in effect on this thread
+    pub fn current() -> Self {
+        Options {
+            bounds: (LOWER_BOUND.get(), UPPER_BOUND.get()),
+            widening_delay: WIDENING_DELAY.get(),
+            widening_thresholds: WIDENING_THRESHOLDS.with_borrow(|t| t.clone()),
+            strict: STRICT_EVALUATION.get(),
+        }
+    }
+
+    fn set(&self) {
+        LOWER_BOUND.set(self.bounds.0);
+        UPPER_BOUND.set(self.bounds.1);
+        WIDENING_DELAY.set(self.widening_delay);
+        WIDENING_THRESHOLDS.set(self.widening_thresholds.clone());
+        STRICT_EVALUATION.set(self.strict);
+    }
+
+    // puts the configuration in effect on this thread until the guard is dropped,
+    // when the one it replaced is back
+    pub fn apply(&self) -> Applied {
+        let previous = Options::current();
+        self.set();
+        Applied(previous)
+    }
+}
+
+pub struct Applied(Options);
+
+impl Drop for Applied {
+    fn drop(&mut self) {
+        self.0.set();
+    }
+}
+
+// --- results
+
+#[derive(Debug, Clone)]
+pub struct Execution {
+    pub points: Vec<PointStates>,
+    pub final_state: concrete_semantics::state::State,
+}
+
+// the concrete states observed at a program point, empty if it was never reached
+#[derive(Debug, Clone)]
+pub struct PointStates {
+    pub point: ProgramPoint,
+    pub states: Vec<concrete_semantics::state::State>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis<T: Domain> {
+    pub points: Vec<PointInvariant<T>>,
+    pub exit_state: abstract_semantics::state::State<T>,
+    pub alarms: Vec<Alarm>,
+    // the number of run-time checks made, the ones without an alarm never fail
+    pub checks: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct PointInvariant<T: Domain> {
+    pub point: ProgramPoint,
+    pub invariant: abstract_semantics::state::State<T>,
+}
+
+// --- entry points
+
+// runs the program from the empty state, `None` if it has no final state
+pub fn evaluate(
+    program: &Spanned<Statement>,
+    options: &Options,
+) -> Result<Option<Execution>, Spanned<ArithmeticExprError>> {
+    use concrete_semantics::denote::*;
+    use concrete_semantics::state::*;
+
+    let _applied = options.apply();
+    let induced_function = denote_stmt(lower(program.clone()).node);
+    let Some((final_state, trace)) = induced_function(State::new())? else {
+        return Ok(None);
+    };
+
+    let points = get_program_points(program.clone());
+    debug_assert_eq!(trace.len(), points.len());
+
+    let points = points
+        .into_iter()
+        .zip(trace)
+        .map(|(point, states)| PointStates { point, states })
+        .collect();
+
+    Ok(Some(Execution {
+        points,
+        final_state,
+    }))
+}
+
+pub fn analyze<T: Domain>(program: &Spanned<Statement>, options: &Options) -> Analysis<T> {
+    use abstract_semantics::denote::*;
+    use abstract_semantics::state::*;
+
+    let _applied = options.apply();
+    let induced_function: StateFunction<T> = denote_stmt(lower(program.clone()).node);
+    let (exit_state, inv) = induced_function(State::new());
+
+    let cfg = Cfg::new(program);
+    debug_assert_eq!(inv.len(), cfg.points.len());
+
+    let Checks { alarms, count } = check(&cfg, &inv);
+    let points = cfg
+        .points
+        .into_iter()
+        .zip(inv)
+        .map(|(point, invariant)| PointInvariant { point, invariant })
+        .collect();
+
+    Analysis {
+        points,
+        exit_state,
+        alarms,
+        checks: count,
+    }
+}
+
+// analyzes the program unless `cancel` is set before the analysis is done, which
+// is checked at each iteration of a loop, from another thread
+pub fn try_analyze<T: Domain>(
+    program: &Spanned<Statement>,
+    options: &Options,
+    cancel: &Arc<AtomicBool>,
+) -> Option<Analysis<T>> {
+    use abstract_semantics::denote::*;
+
+    let previous = CANCEL.replace(Some(cancel.clone()));
+    let analysis = panic::catch_unwind(AssertUnwindSafe(|| analyze::<T>(program, options)));
+    CANCEL.set(previous);
+
+    match analysis {
+        Ok(analysis) => Some(analysis),
+        Err(payload) if payload.is::<Cancelled>() => None,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
@@ -0,0 +1,254 @@
+use crate::cli::*;
+use crate::config::*;
+use crate::{apply_settings, report_alarms, report_syntax_errors, set_bounds, syntax_alarms};
+use crate::{EXIT_FAILURE, EXIT_USAGE};
+use rayon::prelude::*;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::exit;
+use whilep::abstract_semantics::check::dedup;
+use whilep::concrete_semantics::machine::*;
+use whilep::domain::constant::*;
+use whilep::domain::interval::*;
+use whilep::output::sarif;
+use whilep::parser::ast::*;
+use whilep::parser::span::*;
+use whilep::types::integer::*;
+use whilep::*;
+
+// how far the concrete run of a file went
+enum Run {
+    Done,
+    Failed(Spanned<ArithmeticExprError>),
+    Stopped(usize),
+}
+
+// what checking a single file found, as far as it got
+struct Summary {
+    file: String,
+    source: Result<String, io::Error>,
+    syntax_errors: Vec<Spanned<SyntaxError>>,
+    alarms: Vec<Alarm>,
+    checks: usize,
+    run: Option<Run>,
+}
+
+impl Summary {
+    fn unknown(&self) -> usize {
+        self.alarms
+            .iter()
+            .filter(|a| a.severity == Severity::Warning)
+            .count()
+    }
+
+    fn errors(&self) -> usize {
+        self.alarms.len() - self.unknown()
+    }
+
+    // a file fails when it does not parse, when some check fails or may fail, or
+    // when it fails at run time
+    fn failed(&self) -> bool {
+        self.source.is_err()
+            || !self.syntax_errors.is_empty()
+            || !self.alarms.is_empty()
+            || matches!(self.run, Some(Run::Failed(_)))
+    }
+}
+
+// checks every `.wp` file under `dir` in parallel, each one with its own settings
+pub fn run_check_dir(
+    dir: &str,
+    domains: &AbstractOptions,
+    format: CheckFormat,
+    config: &Config,
+    seed: Option<u64>,
+) {
+    let mut files = vec![];
+    if let Err(e) = find_programs(Path::new(dir), &mut files) {
+        println!("[ERROR] failed to read {}: {}", dir, e);
+        exit(EXIT_USAGE);
+    }
+    files.sort();
+
+    // the flag is set on this thread only
+    let strict = STRICT_EVALUATION.get();
+    let summaries = files
+        .par_iter()
+        .map(|file| {
+            STRICT_EVALUATION.set(strict);
+            let settings = config.settings(Some(file));
+            check_file(file, &domains.or(&settings), &settings, seed)
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        CheckFormat::Sarif => print_sarif(&summaries),
+        CheckFormat::Text => print_summaries(dir, &summaries),
+    }
+
+    if summaries.iter().any(|s| s.source.is_err()) {
+        exit(EXIT_USAGE);
+    }
+    if summaries.iter().any(Summary::failed) {
+        exit(EXIT_FAILURE);
+    }
+}
+
+fn find_programs(dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_programs(&path, files)?;
+        } else if path.extension().is_some_and(|e| e == "wp") {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+    Ok(())
+}
+
+// runs on a thread of the pool, whose settings are those of the previous file
+fn check_file(
+    file: &str,
+    domains: &AbstractOptions,
+    settings: &Settings,
+    seed: Option<u64>,
+) -> Summary {
+    let mut summary = Summary {
+        file: file.to_string(),
+        source: fs::read_to_string(file),
+        syntax_errors: vec![],
+        alarms: vec![],
+        checks: 0,
+        run: None,
+    };
+    let Ok(source) = &summary.source else {
+        return summary;
+    };
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            summary.syntax_errors = errors;
+            return summary;
+        }
+    };
+
+    apply_settings(settings, seed);
+
+    if domains.selected(DomainName::Interval) {
+        let bounds = domains
+            .bounds
+            .unwrap_or(Bounds::Range(Integer::NegInf, Integer::PosInf));
+        set_bounds(Some(bounds), &ast);
+
+        let analysis = analyze::<Interval>(&ast, &Options::current());
+        summary.alarms.extend(analysis.alarms);
+        summary.checks = analysis.checks;
+    }
+
+    if domains.selected(DomainName::Constant) {
+        let analysis = analyze::<Constant>(&ast, &Options::current());
+        summary.alarms.extend(analysis.alarms);
+        summary.checks = analysis.checks;
+    }
+
+    summary.alarms = dedup(summary.alarms);
+    summary.run = Some(run(&ast, settings.max_steps.unwrap_or(1_000_000)));
+    summary
+}
+
+// runs the program with the small-step machine, to stop it after `max_steps`
+fn run(ast: &Spanned<Statement>, max_steps: usize) -> Run {
+    let mut machine = Machine::new(ast);
+    let mut steps = 0;
+
+    while machine.current().is_some() {
+        if steps == max_steps {
+            return Run::Stopped(steps);
+        }
+        if let Err(e) = machine.step() {
+            return Run::Failed(e);
+        }
+        steps += 1;
+    }
+    Run::Done
+}
+
+// --- output
+
+fn print_summaries(dir: &str, summaries: &[Summary]) {
+    for summary in summaries {
+        match (&summary.source, summary.syntax_errors.is_empty()) {
+            (Err(e), _) => println!("[ERROR] failed to read {}: {}", summary.file, e),
+            (Ok(source), true) => report_alarms(&summary.file, source, &summary.alarms),
+            (Ok(source), _) => report_syntax_errors(&summary.file, source, &summary.syntax_errors),
+        }
+    }
+
+    if summaries.is_empty() {
+        info!("found no .wp file in {}", dir);
+        return;
+    }
+
+    let headers = ["File", "Parse", "Errors", "Unknown", "Proven", "Run"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+    let rows = summaries.iter().map(summary_row).collect();
+    draw_table(headers, rows);
+
+    let failed = summaries.iter().filter(|s| s.failed()).count();
+    info!(
+        "checked {} file(s) in {}, {} failed",
+        summaries.len(),
+        dir,
+        failed
+    );
+}
+
+fn summary_row(summary: &Summary) -> Vec<String> {
+    let parse = match (&summary.source, summary.syntax_errors.len()) {
+        (Err(_), _) => "unreadable".to_string(),
+        (_, 0) => "ok".to_string(),
+        (_, n) => format!("{} error(s)", n),
+    };
+    let run = match &summary.run {
+        None => "-".to_string(),
+        Some(Run::Done) => "ok".to_string(),
+        Some(Run::Failed(e)) => format!("{} at {}:{}", e, e.span.line, e.span.column),
+        Some(Run::Stopped(steps)) => format!("stopped after {} steps", steps),
+    };
+
+    let counts = match &summary.run {
+        None => vec!["-".to_string(); 3],
+        Some(_) => vec![
+            summary.errors().to_string(),
+            summary.unknown().to_string(),
+            (summary.checks - summary.alarms.len()).to_string(),
+        ],
+    };
+
+    [vec![summary.file.clone(), parse], counts, vec![run]].concat()
+}
+
+// a single sarif log for the whole directory, syntax errors included
+fn print_sarif(summaries: &[Summary]) {
+    let files = summaries
+        .iter()
+        .filter_map(|summary| {
+            let source = summary.source.as_ref().ok()?;
+            let alarms = match summary.syntax_errors.is_empty() {
+                true => summary.alarms.clone(),
+                _ => syntax_alarms(&summary.syntax_errors),
+            };
+            Some((summary.file.as_str(), source.as_str(), alarms))
+        })
+        .collect::<Vec<_>>();
+
+    let files = files
+        .iter()
+        .map(|(file, source, alarms)| (*file, *source, alarms.as_slice()))
+        .collect::<Vec<_>>();
+    let log = sarif::log_files(&files);
+    println!("{}", serde_json::to_string_pretty(&log).unwrap());
+}
@@ -1,40 +1,312 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use cli_tables::Table;
+use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use whilep::domain::interval::*;
+use whilep::fuzz::generator::GeneratorOptions;
+use whilep::types::integer::*;
+
+// when set, the `[INFO]` lines are not printed
+pub static QUIET: AtomicBool = AtomicBool::new(false);
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct ProgramOptions {
-    #[arg(
+    #[command(subcommand)]
+    pub command: Command,
+
+    #[clap(
         short,
-        long = "source-file",
-        required = true,
-        help = "Path to the source file"
+        long,
+        global = true,
+        action,
+        help = "Do not print the [INFO] lines"
     )]
-    pub source_file: String,
-
-    #[clap(long, action, help = "Perform a concrete evaluation")]
-    pub eval: bool,
+    pub quiet: bool,
 
     #[clap(
         long,
+        global = true,
         action,
-        help = "Perform an abstract evaluation on the interval domain"
+        help = "Evaluate both operands of && and || instead of short-circuiting"
     )]
-    pub check_interval: bool,
+    pub strict: bool,
 
     #[clap(
         long,
-        action,
-        help = "Perform an abstract evaluation on the constant domain"
+        global = true,
+        help = "Seed of the values drawn from `[a, b]` and of the programs of `fuzz`, random by default"
     )]
-    pub check_constant: bool,
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Table,
+    Json,
+    Annotated,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum CheckFormat {
+    Text,
+    Sarif,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DomainName {
+    Interval,
+    Constant,
+}
+
+// the bounds of the interval domain, `auto` taking the largest number of the program
+#[derive(Clone, Copy, PartialEq)]
+pub enum Bounds {
+    Auto,
+    Range(Integer, Integer),
+}
+
+fn parse_bounds(bounds: &str) -> Result<Bounds, String> {
+    match bounds {
+        "auto" => Ok(Bounds::Auto),
+        _ => match Interval::from_str(bounds) {
+            Ok(Interval::Range(min, max)) if min <= max => Ok(Bounds::Range(min, max)),
+            Ok(_) => Err("the lower bound is greater than the upper one".to_string()),
+            Err(_) => {
+                Err("expected `auto` or `[a, b]` with integers, `neginf` or `posinf`".to_string())
+            }
+        },
+    }
+}
+
+// written in whilep.toml as on the command line
+impl<'de> Deserialize<'de> for Bounds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_bounds(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Args, Clone)]
+pub struct AbstractOptions {
+    #[arg(
+        short,
+        long = "domain",
+        value_enum,
+        value_delimiter = ',',
+        help = "Abstract domains to use, all of them by default"
+    )]
+    pub domains: Vec<DomainName>,
 
     #[arg(
-        short = 'b',
+        short,
         long,
-        help = "Set the lower and upper bounds for the interval domain"
+        value_parser = parse_bounds,
+        help = "Bounds of the interval domain, `auto` or `[a, b]`"
     )]
-    pub bounds: Option<String>,
+    pub bounds: Option<Bounds>,
+}
+
+impl AbstractOptions {
+    pub fn selected(&self, domain: DomainName) -> bool {
+        self.domains.is_empty() || self.domains.contains(&domain)
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Evaluate a program with the concrete semantics")]
+    Run {
+        #[arg(help = "Path to the source file")]
+        file: String,
+
+        #[arg(
+            long,
+            value_enum,
+            help = "Output format of the results, `table` by default"
+        )]
+        format: Option<Format>,
+    },
+
+    #[command(about = "Evaluate a program with the abstract semantics")]
+    Analyze {
+        #[arg(help = "Path to the source file")]
+        file: String,
+
+        #[command(flatten)]
+        domains: AbstractOptions,
+
+        #[arg(
+            long,
+            value_enum,
+            help = "Output format of the results, `table` by default"
+        )]
+        format: Option<Format>,
+    },
+
+    #[command(about = "Report what may go wrong when running a program")]
+    Check {
+        #[arg(help = "Path to the source file, or to a directory of .wp files")]
+        file: String,
+
+        #[command(flatten)]
+        domains: AbstractOptions,
+
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = CheckFormat::Text,
+            help = "Output format of the alarms"
+        )]
+        format: CheckFormat,
+    },
+
+    #[command(about = "List the program points of a program")]
+    Points {
+        #[arg(help = "Path to the source file")]
+        file: String,
+    },
+
+    #[command(about = "Print the control-flow graph of a program in the dot language")]
+    Dot {
+        #[arg(help = "Path to the source file")]
+        file: String,
+
+        #[arg(
+            short,
+            long = "domain",
+            value_enum,
+            value_delimiter = ',',
+            help = "Abstract domains whose states label the nodes, the concrete states by default"
+        )]
+        domains: Vec<DomainName>,
+
+        #[arg(
+            short,
+            long,
+            value_parser = parse_bounds,
+            help = "Bounds of the interval domain, `auto` or `[a, b]`"
+        )]
+        bounds: Option<Bounds>,
+    },
+
+    #[command(about = "Write a self-contained HTML report of a program")]
+    Report {
+        #[arg(help = "Path to the source file")]
+        file: String,
+
+        #[arg(short, long, help = "Path to the HTML file to write")]
+        output: String,
+
+        #[command(flatten)]
+        domains: AbstractOptions,
+    },
+
+    #[command(about = "Format source files in place")]
+    Fmt {
+        #[arg(required = true, help = "Paths to the source files")]
+        files: Vec<String>,
+
+        #[clap(
+            long,
+            action,
+            help = "Report unformatted files instead of rewriting them"
+        )]
+        check: bool,
+    },
+
+    #[command(about = "Compare the analyses with the concrete semantics on random programs")]
+    Fuzz {
+        #[arg(long, default_value_t = 1000, help = "Number of programs to generate")]
+        count: u64,
+
+        #[arg(
+            long,
+            default_value_t = GeneratorOptions::default().size,
+            help = "Most statements of a program, not counting the ones driving its loops"
+        )]
+        size: usize,
+
+        #[arg(
+            long,
+            default_value_t = GeneratorOptions::default().depth,
+            help = "Most loops nested in one another"
+        )]
+        depth: usize,
+
+        #[arg(
+            long,
+            default_value_t = GeneratorOptions::default().variables,
+            help = "Number of variables besides the loop counters"
+        )]
+        variables: usize,
+
+        #[clap(long, action, help = "Do not draw numbers from `[a, b]`")]
+        no_intervals: bool,
+
+        #[clap(long, action, help = "Do not divide")]
+        no_division: bool,
+
+        #[command(flatten)]
+        domains: AbstractOptions,
+    },
+
+    #[command(about = "Run a language server over stdio")]
+    Lsp,
+
+    #[command(about = "Run a debug adapter over stdio")]
+    Dap,
+
+    #[command(about = "Run statements interactively against a persistent state")]
+    Repl {
+        #[arg(
+            short,
+            long,
+            value_parser = parse_bounds,
+            help = "Bounds of the interval domain, `auto` or `[a, b]`"
+        )]
+        bounds: Option<Bounds>,
+    },
+
+    #[command(about = "Serve a JSON API over HTTP on localhost")]
+    Serve {
+        #[arg(long, default_value_t = 8080, help = "Port to listen on")]
+        port: u16,
+
+        #[arg(
+            long,
+            help = "Most steps a single evaluation may run, 1000000 by default"
+        )]
+        max_steps: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Most milliseconds a single request may run, 5000 by default"
+        )]
+        timeout: Option<u64>,
+    },
+}
+
+impl Command {
+    // the program the command works on, to look up its overrides in whilep.toml
+    pub fn file(&self) -> Option<&str> {
+        match self {
+            Command::Run { file, .. }
+            | Command::Analyze { file, .. }
+            | Command::Check { file, .. }
+            | Command::Points { file }
+            | Command::Dot { file, .. }
+            | Command::Report { file, .. } => Some(file),
+            Command::Fmt { .. }
+            | Command::Lsp
+            | Command::Dap
+            | Command::Repl { .. }
+            | Command::Serve { .. }
+            | Command::Fuzz { .. } => None,
+        }
+    }
 }
 
 pub fn parse_options() -> ProgramOptions {
@@ -1,99 +1,104 @@
 use crate::concrete_semantics::state::*;
+use crate::concrete_semantics::trace::*;
 use crate::parser::ast::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
 use crate::types::integer::*;
 
 // --- type aliases
 
-type IntResult = Result<(Integer, State), ArithmeticExprError>;
-type BoolResult = Result<(bool, State), ArithmeticExprError>;
-type StateResult = Result<Option<State>, ArithmeticExprError>;
+type IntResult = Result<(Integer, State), Spanned<ArithmeticExprError>>;
+type BoolResult = Result<(bool, State), Spanned<ArithmeticExprError>>;
+type StateResult = Result<Option<(State, Trace)>, Spanned<ArithmeticExprError>>;
 
-type StateFunction = Box<dyn Fn(State) -> StateResult>;
+pub type StateFunction = Box<dyn Fn(State) -> StateResult>;
 type Functional = Box<dyn Fn(StateFunction) -> StateFunction>;
 
-trait FunctionMethods {
-    fn compose_many(&self, n: i32, input: StateFunction) -> StateFunction;
-}
-
-impl FunctionMethods for Functional {
-    fn compose_many(&self, n: i32, input: StateFunction) -> StateFunction {
-        match n {
-            0 => input,
-            _ => self.compose_many(n - 1, self(input)),
-        }
-    }
-}
-
 // --- ast denotation
 
 pub fn denote_stmt(stmt: Statement) -> StateFunction {
     match stmt {
-        Statement::Skip => id(),
-        Statement::Chain(s1, s2) => compose(denote_stmt(*s1), denote_stmt(*s2)),
-        Statement::Assignment { var, val } => state_update(var, *val),
-        Statement::If { cond, s1, s2 } => conditional(*cond, denote_stmt(*s1), denote_stmt(*s2)),
-        Statement::While { cond, body, .. } => {
-            let f = Box::new(move |g| {
-                conditional(*cond.clone(), compose(denote_stmt(*body.clone()), g), id())
-            });
-            fix(f)
+        Statement::Skip => observe(id()),
+        Statement::Error(_) => id(),
+        Statement::Chain(s1, s2) => compose(denote_stmt(s1.node), denote_stmt(s2.node)),
+        Statement::Assignment { var, val } => observe(state_update(var, *val)),
+        Statement::If { cond, s1, s2 } => {
+            let points = (count_points(&s1), count_points(&s2));
+            conditional(*cond, denote_stmt(s1.node), denote_stmt(s2.node), points)
         }
-        Statement::RepeatUntil { body, cond, .. } => {
+        Statement::While { cond, body, .. } => {
+            let points = count_points(&body);
             let f = Box::new(move |g| {
-                compose(
-                    denote_stmt(*body.clone()),
-                    conditional(*cond.clone(), id(), g),
-                )
+                loop_iteration(*cond.clone(), denote_stmt(body.node.clone()), g, points)
             });
             fix(f)
         }
+        // surface constructs are rewritten by `lower` before reaching the semantics
+        _ => unreachable!("[ERROR] statement was not lowered: {}", stmt),
     }
 }
 
-pub fn eval_aexpr(expr: &ArithmeticExpr, state: &State) -> IntResult {
-    match expr {
+pub fn eval_aexpr(expr: &Spanned<ArithmeticExpr>, state: &State) -> IntResult {
+    let error = |e| Spanned::new(e, expr.span);
+
+    match &expr.node {
         ArithmeticExpr::Number(n) => Ok((*n, state.clone())),
         ArithmeticExpr::Interval(a1, a2) => {
-            let (a1_val, new_state) = eval_aexpr(a1, &state)?;
+            let (a1_val, new_state) = eval_aexpr(a1, state)?;
             let (a2_val, new_state) = eval_aexpr(a2, &new_state)?;
             match a1_val <= a2_val {
                 true => Ok((random_integer_between(a1_val, a2_val), new_state)),
-                _ => Err(ArithmeticExprError::InvalidIntervalBounds),
+                _ => Err(error(ArithmeticExprError::InvalidIntervalBounds)),
             }
         }
         ArithmeticExpr::Variable(var) => match state.read(var) {
             Ok(val) => Ok((val, state.clone())),
-            Err(err) => Err(err),
+            Err(err) => Err(error(err)),
         },
-        ArithmeticExpr::Add(a1, a2) => binop_aexpr(|a, b| a + b, a1, a2, state),
-        ArithmeticExpr::Sub(a1, a2) => binop_aexpr(|a, b| a - b, a1, a2, state),
-        ArithmeticExpr::Mul(a1, a2) => binop_aexpr(|a, b| a * b, a1, a2, state),
+        ArithmeticExpr::Add(a1, a2) => binop_aexpr(Integer::checked_add, a1, a2, state),
+        ArithmeticExpr::Sub(a1, a2) => binop_aexpr(Integer::checked_sub, a1, a2, state),
+        ArithmeticExpr::Mul(a1, a2) => binop_aexpr(Integer::checked_mul, a1, a2, state),
         ArithmeticExpr::Div(a1, a2) => {
-            let (a1_val, new_state) = eval_aexpr(a1, &state)?;
+            let (a1_val, new_state) = eval_aexpr(a1, state)?;
             let (a2_val, new_state) = eval_aexpr(a2, &new_state)?;
             match a2_val {
-                ZERO => Err(ArithmeticExprError::DivByZero),
-                _ => Ok((a1_val / a2_val, new_state)),
+                ZERO => Err(error(ArithmeticExprError::DivByZero)),
+                _ => match a1_val.checked_div(a2_val) {
+                    Some(val) => Ok((val, new_state)),
+                    None => Err(error(ArithmeticExprError::Overflow)),
+                },
             }
         }
         ArithmeticExpr::PostIncrement(var) => match state.read(var) {
-            Ok(val) => Ok((val, state.put(var, val + 1))),
-            Err(err) => Err(err),
+            Ok(val) => match val.checked_add(ONE) {
+                Some(next) => Ok((val, state.put(var, next))),
+                None => Err(error(ArithmeticExprError::Overflow)),
+            },
+            Err(err) => Err(error(err)),
         },
         ArithmeticExpr::PostDecrement(var) => match state.read(var) {
-            Ok(val) => Ok((val, state.put(var, val - 1))),
-            Err(err) => Err(err),
+            Ok(val) => match val.checked_sub(ONE) {
+                Some(next) => Ok((val, state.put(var, next))),
+                None => Err(error(ArithmeticExprError::Overflow)),
+            },
+            Err(err) => Err(error(err)),
         },
     }
 }
 
-pub fn eval_bexpr(expr: &BooleanExpr, state: &State) -> BoolResult {
-    match expr {
+pub fn eval_bexpr(expr: &Spanned<BooleanExpr>, state: &State) -> BoolResult {
+    match &expr.node {
         BooleanExpr::True => Ok((true, state.clone())),
         BooleanExpr::False => Ok((false, state.clone())),
-        BooleanExpr::Not(b) => eval_bexpr(&b.negate(), state),
-        BooleanExpr::And(b1, b2) => binop_bexpr(|a, b| a && b, b1, b2, state),
-        BooleanExpr::Or(b1, b2) => binop_bexpr(|a, b| a || b, b1, b2, state),
+        BooleanExpr::Not(b) => eval_bexpr(&b.map(|b| b.negate()), state),
+        BooleanExpr::And(b1, b2) => match STRICT_EVALUATION.get() {
+            true => binop_bexpr(|a, b| a && b, b1, b2, state),
+            _ => short_circuit_bexpr(false, b1, b2, state),
+        },
+        BooleanExpr::Or(b1, b2) => match STRICT_EVALUATION.get() {
+            true => binop_bexpr(|a, b| a || b, b1, b2, state),
+            _ => short_circuit_bexpr(true, b1, b2, state),
+        },
         BooleanExpr::NumEq(a1, a2) => binop_cmp(|a, b| a == b, a1, a2, state),
         BooleanExpr::NumNotEq(a1, a2) => binop_cmp(|a, b| a != b, a1, a2, state),
         BooleanExpr::NumLt(a1, a2) => binop_cmp(|a, b| a < b, a1, a2, state),
@@ -110,29 +115,102 @@ fn bottom() -> StateFunction {
 }
 
 fn id() -> StateFunction {
-    Box::new(|state| Ok(Some(state)))
+    Box::new(|state| Ok(Some((state, Trace::new()))))
+}
+
+fn observe(f: StateFunction) -> StateFunction {
+    Box::new(move |state| match f(state)? {
+        Some((new_state, trace)) => {
+            let trace = concat(vec![trace, single(&new_state)]);
+            Ok(Some((new_state, trace)))
+        }
+        None => Ok(None),
+    })
 }
 
 fn compose(f: StateFunction, g: StateFunction) -> StateFunction {
-    Box::new(move |state| match f(state.clone()) {
-        Ok(Some(new_state)) => g(new_state),
-        Ok(None) => Ok(None),
-        Err(e) => Err(e),
+    Box::new(move |state| match f(state)? {
+        Some((f_state, f_trace)) => match g(f_state)? {
+            Some((g_state, g_trace)) => Ok(Some((g_state, concat(vec![f_trace, g_trace])))),
+            None => Ok(None),
+        },
+        None => Ok(None),
     })
 }
 
-fn state_update(var: Identifier, val: ArithmeticExpr) -> StateFunction {
+fn state_update(var: Identifier, val: Spanned<ArithmeticExpr>) -> StateFunction {
     Box::new(move |state| match eval_aexpr(&val, &state) {
-        Ok((val, new_state)) => Ok(Some(new_state.put(&var, val))),
+        Ok((val, new_state)) => Ok(Some((new_state.put(&var, val), Trace::new()))),
         Err(e) => Err(e),
     })
 }
 
-fn conditional(cond: BooleanExpr, s1: StateFunction, s2: StateFunction) -> StateFunction {
-    Box::new(move |state| match eval_bexpr(&cond, &state) {
-        Ok((true, new_state)) => s1(new_state),
-        Ok((false, new_state)) => s2(new_state),
-        Err(e) => Err(e),
+fn conditional(
+    cond: Spanned<BooleanExpr>,
+    s1: StateFunction,
+    s2: StateFunction,
+    (s1_points, s2_points): (usize, usize),
+) -> StateFunction {
+    Box::new(move |state| {
+        let (branch, new_state) = eval_bexpr(&cond, &state)?;
+        let taken = match branch {
+            true => s1(new_state.clone())?,
+            false => s2(new_state.clone())?,
+        };
+
+        Ok(taken.map(|(end_state, trace)| {
+            let trace = match branch {
+                true => concat(vec![
+                    single(&new_state),
+                    trace,
+                    empty(1),
+                    empty(s2_points),
+                    single(&end_state),
+                ]),
+                false => concat(vec![
+                    empty(1),
+                    empty(s1_points),
+                    single(&new_state),
+                    trace,
+                    single(&end_state),
+                ]),
+            };
+            (end_state, trace)
+        }))
+    })
+}
+
+fn loop_iteration(
+    cond: Spanned<BooleanExpr>,
+    body: StateFunction,
+    g: StateFunction,
+    body_points: usize,
+) -> StateFunction {
+    Box::new(move |state| match eval_bexpr(&cond, &state)? {
+        (true, cond_state) => {
+            let Some((body_state, body_trace)) = body(cond_state.clone())? else {
+                return Ok(None);
+            };
+            let Some((end_state, next_trace)) = g(body_state)? else {
+                return Ok(None);
+            };
+            let trace = concat(vec![
+                single(&state),
+                single(&cond_state),
+                body_trace,
+                empty(1),
+            ]);
+            Ok(Some((end_state, merge(trace, next_trace))))
+        }
+        (false, exit_state) => {
+            let trace = concat(vec![
+                single(&state),
+                empty(1),
+                empty(body_points),
+                single(&exit_state),
+            ]);
+            Ok(Some((exit_state, trace)))
+        }
     })
 }
 
@@ -142,7 +220,7 @@ fn fix(f: Functional) -> StateFunction {
         loop {
             g = f(g);
             match g(state.clone()) {
-                Ok(Some(state)) => return Ok(Some(state)),
+                Ok(Some(result)) => return Ok(Some(result)),
                 Ok(None) => continue,
                 Err(e) => return Err(e),
             }
@@ -152,35 +230,58 @@ fn fix(f: Functional) -> StateFunction {
 
 // --- helpers
 
+fn count_points(stmt: &Spanned<Statement>) -> usize {
+    get_program_points(stmt.clone()).len()
+}
+
+// `op` gives `None` when the result is out of range
 fn binop_aexpr(
-    op: fn(Integer, Integer) -> Integer,
-    a1: &ArithmeticExpr,
-    a2: &ArithmeticExpr,
+    op: fn(Integer, Integer) -> Option<Integer>,
+    a1: &Spanned<ArithmeticExpr>,
+    a2: &Spanned<ArithmeticExpr>,
     state: &State,
 ) -> IntResult {
-    let (a1_val, new_state) = eval_aexpr(a1, &state)?;
+    let (a1_val, new_state) = eval_aexpr(a1, state)?;
     let (a2_val, new_state) = eval_aexpr(a2, &new_state)?;
-    Ok((op(a1_val, a2_val), new_state))
+    match op(a1_val, a2_val) {
+        Some(val) => Ok((val, new_state)),
+        None => Err(Spanned::new(
+            ArithmeticExprError::Overflow,
+            a1.span.merge(&a2.span),
+        )),
+    }
 }
 
 fn binop_bexpr(
     op: fn(bool, bool) -> bool,
-    b1: &BooleanExpr,
-    b2: &BooleanExpr,
+    b1: &Spanned<BooleanExpr>,
+    b2: &Spanned<BooleanExpr>,
     state: &State,
 ) -> BoolResult {
-    let (b1_val, new_state) = eval_bexpr(b1, &state)?;
+    let (b1_val, new_state) = eval_bexpr(b1, state)?;
     let (b2_val, new_state) = eval_bexpr(b2, &new_state)?;
     Ok((op(b1_val, b2_val), new_state))
 }
 
+fn short_circuit_bexpr(
+    stop_at: bool,
+    b1: &Spanned<BooleanExpr>,
+    b2: &Spanned<BooleanExpr>,
+    state: &State,
+) -> BoolResult {
+    match eval_bexpr(b1, state)? {
+        (b1_val, new_state) if b1_val == stop_at => Ok((b1_val, new_state)),
+        (_, new_state) => eval_bexpr(b2, &new_state),
+    }
+}
+
 fn binop_cmp(
     op: fn(Integer, Integer) -> bool,
-    a1: &ArithmeticExpr,
-    a2: &ArithmeticExpr,
+    a1: &Spanned<ArithmeticExpr>,
+    a2: &Spanned<ArithmeticExpr>,
     state: &State,
 ) -> BoolResult {
-    let (a1_val, new_state) = eval_aexpr(a1, &state)?;
+    let (a1_val, new_state) = eval_aexpr(a1, state)?;
     let (a2_val, new_state) = eval_aexpr(a2, &new_state)?;
     Ok((op(a1_val, a2_val), new_state))
 }
@@ -0,0 +1,233 @@
+use crate::concrete_semantics::denote::*;
+use crate::concrete_semantics::state::*;
+use crate::parser::ast::*;
+use crate::parser::lower::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+
+// a place where the machine pauses, before running a statement or evaluating a
+// condition; `point` is the program point of the action in `get_program_points`
+// and `depth` the number of loop and if bodies around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stop {
+    pub point: usize,
+    pub span: Span,
+    pub depth: usize,
+}
+
+enum Action {
+    Simple(Spanned<Statement>),
+    If {
+        cond: Spanned<BooleanExpr>,
+        s1: Spanned<Statement>,
+        s2: Spanned<Statement>,
+        s1_point: usize,
+        s2_point: usize,
+    },
+    While {
+        stmt: Spanned<Statement>,
+        point: usize,
+    },
+    Until {
+        stmt: Spanned<Statement>,
+        point: usize,
+    },
+    ForInit {
+        var: Identifier,
+        from: Spanned<ArithmeticExpr>,
+    },
+    ForGuard {
+        stmt: Spanned<Statement>,
+        point: usize,
+    },
+}
+
+enum Task {
+    // a statement to unfold, whose first program point is the given one
+    Run(Spanned<Statement>, usize, usize),
+    Do(Action, Stop),
+}
+
+// a small-step interpreter for the concrete semantics, running the surface program
+// one statement or condition at a time instead of through the fixpoints of `denote`
+pub struct Machine {
+    pub state: State,
+    tasks: Vec<Task>,
+}
+
+impl Machine {
+    pub fn new(program: &Spanned<Statement>) -> Self {
+        let mut machine = Machine {
+            state: State::new(),
+            tasks: vec![Task::Run(program.clone(), 0, 0)],
+        };
+        machine.settle();
+        machine
+    }
+
+    // where the machine is paused, `None` once the program has terminated
+    pub fn current(&self) -> Option<Stop> {
+        match self.tasks.last() {
+            Some(Task::Do(_, stop)) => Some(*stop),
+            _ => None,
+        }
+    }
+
+    // runs the action the machine is paused at
+    pub fn step(&mut self) -> Result<(), Spanned<ArithmeticExprError>> {
+        let Some(Task::Do(action, stop)) = self.tasks.pop() else {
+            return Ok(());
+        };
+        let depth = stop.depth;
+
+        match action {
+            Action::Simple(stmt) => {
+                if let Statement::Assignment { var, val } = lower(stmt).node {
+                    let (val, new_state) = eval_aexpr(&val, &self.state)?;
+                    self.state = new_state.put(&var, val);
+                }
+            }
+
+            Action::If {
+                cond,
+                s1,
+                s2,
+                s1_point,
+                s2_point,
+            } => {
+                let (branch, new_state) = eval_bexpr(&cond, &self.state)?;
+                self.state = new_state;
+                match branch {
+                    true => self.tasks.push(Task::Run(s1, s1_point, depth + 1)),
+                    _ => self.tasks.push(Task::Run(s2, s2_point, depth + 1)),
+                }
+            }
+
+            Action::While { stmt, point } => {
+                let Statement::While { cond, body, .. } = &stmt.node else {
+                    unreachable!()
+                };
+                let (enter, new_state) = eval_bexpr(cond, &self.state)?;
+                self.state = new_state;
+                if enter {
+                    let body = *body.clone();
+                    self.tasks.push(Task::Run(stmt, point, depth));
+                    self.tasks.push(Task::Run(body, point + 2, depth + 1));
+                }
+            }
+
+            Action::Until { stmt, point } => {
+                let Statement::RepeatUntil { cond, body, .. } = &stmt.node else {
+                    unreachable!()
+                };
+                let (exit, new_state) = eval_bexpr(cond, &self.state)?;
+                self.state = new_state;
+                if !exit {
+                    // the iterations after the first run the second copy of the body
+                    let points = get_program_points(*body.clone()).len();
+                    let body = *body.clone();
+                    self.tasks
+                        .push(Task::Do(Action::Until { stmt, point }, stop));
+                    self.tasks
+                        .push(Task::Run(body, point + points + 2, depth + 1));
+                }
+            }
+
+            Action::ForInit { var, from } => {
+                let (val, new_state) = eval_aexpr(&from, &self.state)?;
+                self.state = new_state.put(&var, val);
+            }
+
+            Action::ForGuard { stmt, point } => {
+                let Statement::For { var, to, body, .. } = &stmt.node else {
+                    unreachable!()
+                };
+                let (enter, new_state) = eval_bexpr(&for_guard(var, to, stmt.span), &self.state)?;
+                self.state = new_state;
+                if enter {
+                    let points = get_program_points(*body.clone()).len();
+                    let step = Spanned::new(Statement::Increment(var.clone()), stmt.span);
+                    let body = *body.clone();
+                    self.tasks
+                        .push(Task::Do(Action::ForGuard { stmt, point }, stop));
+                    self.tasks.push(Task::Do(
+                        Action::Simple(step),
+                        Stop {
+                            point: point + points + 3,
+                            ..stop
+                        },
+                    ));
+                    self.tasks.push(Task::Run(body, point + 3, depth + 1));
+                }
+            }
+        }
+
+        self.settle();
+        Ok(())
+    }
+
+    // unfolds compound statements until the next action is on top
+    fn settle(&mut self) {
+        while let Some(Task::Run(..)) = self.tasks.last() {
+            let Some(Task::Run(stmt, point, depth)) = self.tasks.pop() else {
+                unreachable!()
+            };
+            let stop = |point, span| Stop { point, span, depth };
+            let span = stmt.span;
+
+            match stmt.node.clone() {
+                Statement::Error(_) => (),
+
+                Statement::Chain(s1, s2) => {
+                    let s1_points = get_program_points(*s1.clone()).len();
+                    self.tasks.push(Task::Run(*s2, point + s1_points, depth));
+                    self.tasks.push(Task::Run(*s1, point, depth));
+                }
+
+                Statement::If { cond, s1, s2 } => {
+                    let s1_points = get_program_points(*s1.clone()).len();
+                    let cond_span = cond.span;
+                    let action = Action::If {
+                        cond: *cond,
+                        s1: *s1,
+                        s2: *s2,
+                        s1_point: point + 1,
+                        s2_point: point + s1_points + 2,
+                    };
+                    self.tasks.push(Task::Do(action, stop(point, cond_span)));
+                }
+
+                Statement::While { ref cond, .. } => {
+                    let cond_span = cond.span;
+                    let action = Action::While { stmt, point };
+                    self.tasks
+                        .push(Task::Do(action, stop(point + 1, cond_span)));
+                }
+
+                Statement::RepeatUntil { body, cond, .. } => {
+                    let points = get_program_points(*body.clone()).len();
+                    let action = Action::Until { stmt, point };
+                    self.tasks
+                        .push(Task::Do(action, stop(point + points + 1, cond.span)));
+                    self.tasks.push(Task::Run(*body, point, depth + 1));
+                }
+
+                Statement::For {
+                    ref var, ref from, ..
+                } => {
+                    let init = Action::ForInit {
+                        var: var.clone(),
+                        from: *from.clone(),
+                    };
+                    let guard = Action::ForGuard { stmt, point };
+                    self.tasks.push(Task::Do(guard, stop(point + 2, span)));
+                    self.tasks.push(Task::Do(init, stop(point, span)));
+                }
+
+                _ => self
+                    .tasks
+                    .push(Task::Do(Action::Simple(stmt), stop(point, span))),
+            }
+        }
+    }
+}
@@ -1,2 +1,4 @@
 pub mod denote;
+pub mod machine;
 pub mod state;
+pub mod trace;
@@ -1,20 +1,16 @@
-use crate::{types::integer::*, ArithmeticExprError, Identifier};
+use crate::parser::ast::{ArithmeticExprError, Identifier};
+use crate::types::integer::*;
 use std::collections::HashMap;
 
 pub type State = HashMap<Identifier, Integer>;
 
 pub trait StateOperations {
-    fn new() -> State;
     fn read(&self, var: &Identifier) -> Result<Integer, ArithmeticExprError>;
     fn put(&self, var: &Identifier, val: Integer) -> State;
-    fn pretty_print(&self);
+    fn pretty(&self) -> String;
 }
 
 impl StateOperations for State {
-    fn new() -> State {
-        HashMap::new()
-    }
-
     fn read(&self, var: &Identifier) -> Result<Integer, ArithmeticExprError> {
         match self.get(var) {
             Some(val) => Ok(*val),
@@ -28,10 +24,9 @@ impl StateOperations for State {
         new_state
     }
 
-    fn pretty_print(&self) {
+    fn pretty(&self) -> String {
         if self.is_empty() {
-            println!("empty state");
-            return;
+            return "empty state".to_string();
         }
 
         let mut pretty_state = self
@@ -40,6 +35,6 @@ impl StateOperations for State {
             .collect::<Vec<String>>();
 
         pretty_state.sort();
-        println!("{}", pretty_state.join(", "));
+        pretty_state.join(", ")
     }
 }
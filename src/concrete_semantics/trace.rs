@@ -0,0 +1,47 @@
+use crate::concrete_semantics::state::*;
+use crate::parser::ast::Identifier;
+use crate::types::integer::Integer;
+use std::collections::BTreeSet;
+
+pub type Trace = Vec<Vec<State>>;
+
+pub fn empty(points: usize) -> Trace {
+    vec![vec![]; points]
+}
+
+pub fn single(state: &State) -> Trace {
+    vec![vec![state.clone()]]
+}
+
+pub fn concat(others: Vec<Trace>) -> Trace {
+    let mut final_trace = Trace::new();
+    for trace in others {
+        final_trace.extend(trace);
+    }
+    final_trace
+}
+
+pub fn merge(t1: Trace, t2: Trace) -> Trace {
+    debug_assert_eq!(t1.len(), t2.len());
+
+    t1.into_iter()
+        .zip(t2)
+        .map(|(mut seen, other)| {
+            let mut keys: BTreeSet<_> = seen.iter().map(key).collect();
+            for state in other {
+                if keys.insert(key(&state)) {
+                    seen.push(state);
+                }
+            }
+            seen
+        })
+        .collect()
+}
+
+// states are hash maps, which are neither hashable nor ordered, so they are
+// compared through their sorted entries
+fn key(state: &State) -> Vec<(Identifier, Integer)> {
+    let mut entries: Vec<_> = state.iter().map(|(k, v)| (k.clone(), *v)).collect();
+    entries.sort();
+    entries
+}
@@ -0,0 +1,99 @@
+use crate::cli::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+pub const CONFIG_FILE: &str = "whilep.toml";
+
+// the defaults a project can set, each one below the flag of the same meaning
+#[derive(Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    pub domains: Option<Vec<DomainName>>,
+    pub bounds: Option<Bounds>,
+    pub widening_delay: Option<u32>,
+    pub widening_thresholds: Option<Vec<i64>>,
+    pub format: Option<Format>,
+    pub seed: Option<u64>,
+    pub max_steps: Option<usize>,
+    pub timeout: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct Config {
+    defaults: Settings,
+    // the overrides of single programs, by path from the working directory
+    files: HashMap<String, Settings>,
+}
+
+// reads the configuration of the working directory, empty if there is none
+pub fn load_config() -> Result<Config, String> {
+    let text = match fs::read_to_string(CONFIG_FILE) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut table = text
+        .parse::<toml::Table>()
+        .map_err(|e| e.message().to_string())?;
+
+    let files = match table.remove("files") {
+        None => HashMap::new(),
+        Some(files) => files
+            .try_into::<HashMap<String, Settings>>()
+            .map_err(|e| format!("in [files]: {}", e.message()))?
+            .into_iter()
+            .map(|(file, settings)| (normalize(&file), settings))
+            .collect(),
+    };
+    let defaults = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e: toml::de::Error| e.message().to_string())?;
+
+    Ok(Config { defaults, files })
+}
+
+impl Config {
+    // the settings of `file`, its overrides taking precedence over the defaults
+    pub fn settings(&self, file: Option<&str>) -> Settings {
+        match file.and_then(|f| self.files.get(&normalize(f))) {
+            Some(overrides) => overrides.clone().or(&self.defaults),
+            None => self.defaults.clone(),
+        }
+    }
+}
+
+impl Settings {
+    fn or(self, other: &Settings) -> Settings {
+        let other = other.clone();
+        Settings {
+            domains: self.domains.or(other.domains),
+            bounds: self.bounds.or(other.bounds),
+            widening_delay: self.widening_delay.or(other.widening_delay),
+            widening_thresholds: self.widening_thresholds.or(other.widening_thresholds),
+            format: self.format.or(other.format),
+            seed: self.seed.or(other.seed),
+            max_steps: self.max_steps.or(other.max_steps),
+            timeout: self.timeout.or(other.timeout),
+        }
+    }
+}
+
+impl AbstractOptions {
+    // the options given on the command line, completed by the settings
+    pub fn or(&self, settings: &Settings) -> AbstractOptions {
+        AbstractOptions {
+            domains: match self.domains.is_empty() {
+                true => settings.domains.clone().unwrap_or_default(),
+                _ => self.domains.clone(),
+            },
+            bounds: self.bounds.or(settings.bounds),
+        }
+    }
+}
+
+fn normalize(file: &str) -> String {
+    file.trim_start_matches("./").to_string()
+}
@@ -3,6 +3,7 @@ use crate::domain::domain::*;
 use crate::domain::lattice::*;
 use crate::parser::ast::*;
 use crate::types::integer::*;
+use serde::ser::{Serialize, SerializeMap, Serializer};
 use std::fmt;
 use std::ops;
 
@@ -19,6 +20,13 @@ impl Lattice for Constant {
     const UNIT: Self = Constant::Value(ONE);
     const ZERO: Self = Constant::Value(ZERO);
 
+    fn leq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constant::None, _) | (_, Constant::Any) => true,
+            _ => self == other,
+        }
+    }
+
     fn lub(&self, other: &Self) -> Self {
         match (self, other) {
             _ if self == other => *self,
@@ -38,11 +46,11 @@ impl Lattice for Constant {
     }
 
     fn widen(&self, other: &Self) -> Self {
-        other.clone()
+        *other
     }
 
     fn narrow(&self, other: &Self) -> Self {
-        other.clone()
+        *other
     }
 
     fn round(x: &Self) -> Self {
@@ -60,9 +68,12 @@ impl Domain for Constant {
             ArithmeticExpr::Interval(a1, a2) => {
                 let (a1_val, new_state) = Self::eval_aexpr(a1, state);
                 let (a2_val, new_state) = Self::eval_aexpr(a2, &new_state);
-                match a1_val {
-                    _ if a1_val == a2_val => (a1_val, new_state),
-                    _ if a1_val > a2_val => (Constant::None, new_state),
+                match (a1_val, a2_val) {
+                    (Constant::None, _) | (_, Constant::None) => (Constant::None, new_state),
+                    (Constant::Value(a), Constant::Value(b)) if a == b => (a1_val, new_state),
+                    (Constant::Value(a), Constant::Value(b)) if a > b => {
+                        (Constant::None, new_state)
+                    }
                     _ => (Constant::Any, new_state),
                 }
             }
@@ -73,7 +84,7 @@ impl Domain for Constant {
     fn eval_specific_bexpr(expr: &BooleanExpr, state: &State<Self>) -> State<Self> {
         match expr {
             BooleanExpr::NumNotEq(a1, a2) => {
-                let (lhs, new_state) = Self::eval_aexpr(a1, &state);
+                let (lhs, new_state) = Self::eval_aexpr(a1, state);
                 let (rhs, new_state) = Self::eval_aexpr(a2, &new_state);
                 match (lhs, rhs) {
                     (Constant::None, _) | (_, Constant::None) => State::Bottom,
@@ -85,7 +96,7 @@ impl Domain for Constant {
                 }
             }
             BooleanExpr::NumLt(a1, a2) => {
-                let (lhs, new_state) = Self::eval_aexpr(a1, &state);
+                let (lhs, new_state) = Self::eval_aexpr(a1, state);
                 let (rhs, new_state) = Self::eval_aexpr(a2, &new_state);
                 match (lhs, rhs) {
                     (Constant::None, _) | (_, Constant::None) => State::Bottom,
@@ -112,12 +123,27 @@ impl fmt::Display for Constant {
     }
 }
 
+// `{"value": c}`, or the strings `"any"` and `"none"`
+impl Serialize for Constant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Constant::None => serializer.serialize_str("none"),
+            Constant::Value(c) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("value", c)?;
+                map.end()
+            }
+            Constant::Any => serializer.serialize_str("any"),
+        }
+    }
+}
+
 impl ops::Neg for Constant {
     type Output = Self;
 
     fn neg(self) -> Self {
         match self {
-            Constant::Value(a) => Constant::Value(-a),
+            Constant::Value(a) => constant(ZERO.checked_sub(a)),
             _ => self,
         }
     }
@@ -130,7 +156,7 @@ impl ops::Add<Constant> for Constant {
         match (self, other) {
             (Constant::None, _) | (_, Constant::None) => Constant::None,
             (Constant::Any, _) | (_, Constant::Any) => Constant::Any,
-            (Constant::Value(a), Constant::Value(b)) => Constant::Value(a + b),
+            (Constant::Value(a), Constant::Value(b)) => constant(a.checked_add(b)),
         }
     }
 }
@@ -139,7 +165,11 @@ impl ops::Sub<Constant> for Constant {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
-        self + (-other)
+        match (self, other) {
+            (Constant::None, _) | (_, Constant::None) => Constant::None,
+            (Constant::Any, _) | (_, Constant::Any) => Constant::Any,
+            (Constant::Value(a), Constant::Value(b)) => constant(a.checked_sub(b)),
+        }
     }
 }
 
@@ -149,7 +179,7 @@ impl ops::Mul<Constant> for Constant {
     fn mul(self, other: Self) -> Self {
         match (self, other) {
             (Constant::None, _) | (_, Constant::None) => Constant::None,
-            (Constant::Value(a), Constant::Value(b)) => Constant::Value(a * b),
+            (Constant::Value(a), Constant::Value(b)) => constant(a.checked_mul(b)),
             (Constant::Any, Constant::Value(a)) | (Constant::Value(a), Constant::Any)
                 if a == ZERO =>
             {
@@ -168,8 +198,14 @@ impl ops::Div<Constant> for Constant {
             (Constant::None, _) | (_, Constant::None) => Constant::None,
             (_, Constant::Value(b)) if b == ZERO => Constant::None,
             (Constant::Value(a), _) if a == ZERO => Constant::Value(ZERO),
-            (Constant::Value(a), Constant::Value(b)) => Constant::Value(a / b),
+            (Constant::Value(a), Constant::Value(b)) => constant(a.checked_div(b)),
             _ => Constant::Any,
         }
     }
 }
+
+// the result of an operation on two constants, any value when it overflows, as
+// the concrete run fails there
+fn constant(result: Option<Integer>) -> Constant {
+    result.map_or(Constant::Any, Constant::Value)
+}
@@ -2,13 +2,16 @@ use crate::abstract_semantics::state::*;
 use crate::domain::expression_tree::*;
 use crate::domain::lattice::*;
 use crate::parser::ast::*;
+use crate::parser::span::*;
+use crate::types::integer::*;
+use serde::Serialize;
 use std::fmt::{Debug, Display};
 use std::ops::{Add, Div, Mul, Sub};
 use trait_set::trait_set;
 
 trait_set! {
     pub trait Arithmetic = Sized + Add<Self, Output = Self> +Sub<Self, Output = Self> +Mul<Self, Output = Self> +Div<Self, Output = Self>;
-    pub trait DomainProperties = Sized + Display + Clone + Copy + Eq + Debug;
+    pub trait DomainProperties = Sized + Display + Clone + Copy + Eq + Debug + Serialize;
 }
 
 pub trait Domain: DomainProperties + Lattice + Arithmetic {
@@ -41,42 +44,46 @@ pub trait Domain: DomainProperties + Lattice + Arithmetic {
             BooleanExpr::True => state.clone(),
             BooleanExpr::False => State::Bottom,
             BooleanExpr::Not(b) => Self::eval_bexpr(&b.negate(), state),
-            BooleanExpr::And(b1, b2) => {
-                let lhs = Self::eval_bexpr(b1, state);
-                let rhs = Self::eval_bexpr(b2, &lhs);
-                lhs.glb(&rhs)
-            }
-            BooleanExpr::Or(b1, b2) => {
-                let lhs = Self::eval_bexpr(b1, state);
-                let rhs = Self::eval_bexpr(b2, &lhs);
-                lhs.lub(&rhs)
-            }
+            // both operands hold, evaluated in order whether `&&` short-circuits or not
+            BooleanExpr::And(b1, b2) => Self::eval_bexpr(b2, &Self::eval_bexpr(b1, state)),
+            BooleanExpr::Or(b1, b2) => match STRICT_EVALUATION.get() {
+                true => {
+                    // `b2` runs even when `b1` holds, for its side effects only
+                    let lhs = Self::eval_bexpr(b1, state);
+                    let lhs = Self::eval_bexpr(b2, &lhs).lub(&Self::eval_bexpr(&b2.negate(), &lhs));
+                    let rhs = Self::eval_bexpr(b2, &Self::eval_bexpr(&b1.negate(), state));
+                    lhs.lub(&rhs)
+                }
+                _ => {
+                    let lhs = Self::eval_bexpr(b1, state);
+                    let rhs = Self::eval_bexpr(b2, &Self::eval_bexpr(&b1.negate(), state));
+                    lhs.lub(&rhs)
+                }
+            },
             BooleanExpr::NumLtEq(a1, a2) => {
                 let lt = Self::eval_bexpr(&BooleanExpr::NumLt(a1.clone(), a2.clone()), state);
                 let eq = Self::eval_bexpr(&BooleanExpr::NumEq(a1.clone(), a2.clone()), state);
                 lt.lub(&eq)
             }
             BooleanExpr::NumGt(a1, a2) => {
-                Self::eval_bexpr(&BooleanExpr::NumLt(a2.clone(), a1.clone()), state)
+                Self::eval_bexpr(&mirror(BooleanExpr::NumLt, a1, a2), state)
             }
             BooleanExpr::NumGtEq(a1, a2) => {
-                Self::eval_bexpr(&BooleanExpr::NumLtEq(a2.clone(), a1.clone()), state)
+                Self::eval_bexpr(&mirror(BooleanExpr::NumLtEq, a1, a2), state)
             }
 
             BooleanExpr::NumEq(a1, a2) => {
-                let a1_tree = ExpressionTree::build(a1, state).0;
-                let a2_tree = ExpressionTree::build(a2, state).0;
+                let (a1_tree, a2_tree) = ExpressionTree::build_pair(a1, a2, state);
                 let intersection = a1_tree.value().glb(&a2_tree.value());
 
-                match intersection == Self::BOT {
+                match intersection.is_bottom() {
                     true => State::Bottom,
                     _ => {
                         let new_state = a1_tree.refine(intersection, state.clone());
                         let new_state = a2_tree.refine(intersection, new_state);
 
                         let new_state = Self::eval_aexpr(a1, &new_state).1;
-                        let new_state = Self::eval_aexpr(a2, &new_state).1;
-                        new_state
+                        Self::eval_aexpr(a2, &new_state).1
                     }
                 }
             }
@@ -89,13 +96,29 @@ pub trait Domain: DomainProperties + Lattice + Arithmetic {
     }
 }
 
+type Operand = Box<Spanned<ArithmeticExpr>>;
+
+// `a2 cmp a1`, or `-a1 cmp -a2` when swapping the operands would swap their side
+// effects as well
+fn mirror(cmp: fn(Operand, Operand) -> BooleanExpr, a1: &Operand, a2: &Operand) -> BooleanExpr {
+    let negate = |a: &Operand| {
+        let zero = Spanned::boxed(ArithmeticExpr::Number(ZERO), a.span);
+        Spanned::boxed(ArithmeticExpr::Sub(zero, a.clone()), a.span)
+    };
+
+    match a1.written_variables().is_empty() && a2.written_variables().is_empty() {
+        true => cmp(a2.clone(), a1.clone()),
+        _ => cmp(negate(a1), negate(a2)),
+    }
+}
+
 pub fn binop_aexpr<T: Domain>(
     op: fn(T, T) -> T,
     a1: &ArithmeticExpr,
     a2: &ArithmeticExpr,
     state: &State<T>,
 ) -> (T, State<T>) {
-    let (i1, new_state) = T::eval_aexpr(a1, &state);
+    let (i1, new_state) = T::eval_aexpr(a1, state);
     let (i2, new_state) = T::eval_aexpr(a2, &new_state);
     (op(i1, i2), new_state)
 }
@@ -31,8 +31,7 @@ impl<T: Domain> ExpressionTree<T> {
             | ArithmeticExpr::Sub(a1, a2)
             | ArithmeticExpr::Mul(a1, a2)
             | ArithmeticExpr::Div(a1, a2) => {
-                let (l, s) = ExpressionTree::build(a1, &state);
-                let (r, _) = ExpressionTree::build(a2, &s);
+                let (l, r) = ExpressionTree::build_pair(a1, a2, state);
                 (
                     ExpressionTree::Binop {
                         value: val,
@@ -46,6 +45,34 @@ impl<T: Domain> ExpressionTree<T> {
         }
     }
 
+    // the trees of `a1` and of `a2` evaluated after it, as in `a1 op a2`: the
+    // variables `a1` increments or decrements cannot be refined through `a2`, whose
+    // values of them are not the ones of `state`
+    pub fn build_pair(
+        a1: &ArithmeticExpr,
+        a2: &ArithmeticExpr,
+        state: &State<T>,
+    ) -> (ExpressionTree<T>, ExpressionTree<T>) {
+        let (l, s) = ExpressionTree::build(a1, state);
+        let (r, _) = ExpressionTree::build(a2, &s);
+        (l, r.forget(&a1.written_variables()))
+    }
+
+    fn forget(self, vars: &[Identifier]) -> Self {
+        match self {
+            ExpressionTree::Variable(var, value) if vars.contains(&var) => {
+                ExpressionTree::Value(value)
+            }
+            ExpressionTree::Binop { value, op, l, r } => ExpressionTree::Binop {
+                value,
+                op,
+                l: Box::new(l.forget(vars)),
+                r: Box::new(r.forget(vars)),
+            },
+            tree => tree,
+        }
+    }
+
     pub fn refine(&self, refined_value: T, state: State<T>) -> State<T> {
         match self {
             ExpressionTree::Value(_) => state,
@@ -59,15 +86,14 @@ impl<T: Domain> ExpressionTree<T> {
                 let (new_a, new_b) = match op {
                     ArithmeticExpr::Add(_, _) => (c - b, c - a),
                     ArithmeticExpr::Sub(_, _) => (c + b, a - c),
-                    ArithmeticExpr::Mul(_, _) => (c / b, c / a),
-                    ArithmeticExpr::Div(_, _) => (s * b, (a / s).lub(&T::ZERO)),
+                    ArithmeticExpr::Mul(_, _) => (quotient(c, b), quotient(c, a)),
+                    ArithmeticExpr::Div(_, _) => (s * b, divisor(a, c, s)),
                     _ => unreachable!(),
                 };
 
                 let (new_a, new_b) = (a.glb(&new_a), b.glb(&new_b));
                 let l_state = l.refine(new_a, state);
-                let r_state = r.refine(new_b, l_state);
-                r_state
+                r.refine(new_b, l_state)
             }
         }
     }
@@ -76,7 +102,25 @@ impl<T: Domain> ExpressionTree<T> {
         match self {
             ExpressionTree::Value(value)
             | ExpressionTree::Variable(_, value)
-            | ExpressionTree::Binop { value, .. } => value.clone(),
+            | ExpressionTree::Binop { value, .. } => *value,
         }
     }
 }
+
+// the factors whose product by `factor` may be in `product`: any of them when
+// the product and the factor may both be zero
+fn quotient<T: Domain>(product: T, factor: T) -> T {
+    match T::ZERO.leq(&product) && T::ZERO.leq(&factor) {
+        true => T::TOP,
+        _ => product / factor,
+    }
+}
+
+// the divisors of `dividend` whose quotient may be in `quotient`, rounded to `s`:
+// any of them when the quotient may be zero, as it is for every larger divisor
+fn divisor<T: Domain>(dividend: T, quotient: T, s: T) -> T {
+    match T::ZERO.leq(&quotient) {
+        true => T::TOP,
+        _ => (dividend / s).lub(&T::ZERO),
+    }
+}
@@ -4,6 +4,8 @@ use crate::domain::lattice::*;
 use crate::parser::ast::*;
 use crate::types::integer::*;
 use crate::utils::math::*;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::cell::{Cell, RefCell};
 use std::ops::Add;
 use std::{
     cmp, fmt,
@@ -13,8 +15,14 @@ use std::{
 
 use super::expression_tree::ExpressionTree;
 
-pub static mut LOWER_BOUND: Integer = Integer::NegInf;
-pub static mut UPPER_BOUND: Integer = Integer::PosInf;
+// per thread, so that programs analyzed in parallel each have their own
+thread_local! {
+    pub static LOWER_BOUND: Cell<Integer> = const { Cell::new(Integer::NegInf) };
+    pub static UPPER_BOUND: Cell<Integer> = const { Cell::new(Integer::PosInf) };
+
+    // the values an unstable bound is widened to before giving up on it, in any order
+    pub static WIDENING_THRESHOLDS: RefCell<Vec<Integer>> = const { RefCell::new(Vec::new()) };
+}
 
 #[derive(Debug, Clone, Copy, Eq)]
 pub enum Interval {
@@ -52,24 +60,23 @@ impl Interval {
     }
 
     pub fn check_bounds(&self) -> Self {
-        unsafe {
-            match self.clone() {
-                Interval::Empty => Interval::Empty,
-                _ if self.min() == self.max() => *self,
-                Interval::Range(a, b) => {
-                    let min = match a {
-                        _ if a < LOWER_BOUND => Integer::NegInf,
-                        _ if a > UPPER_BOUND => UPPER_BOUND,
-                        _ => a,
-                    };
-                    let max = match b {
-                        _ if b > UPPER_BOUND => Integer::PosInf,
-                        _ if b < LOWER_BOUND => LOWER_BOUND,
-                        _ => b,
-                    };
+        let (lower, upper) = (LOWER_BOUND.get(), UPPER_BOUND.get());
+        match *self {
+            Interval::Empty => Interval::Empty,
+            _ if self.min() == self.max() => *self,
+            Interval::Range(a, b) => {
+                let min = match a {
+                    _ if a < lower => Integer::NegInf,
+                    _ if a > upper => upper,
+                    _ => a,
+                };
+                let max = match b {
+                    _ if b > upper => Integer::PosInf,
+                    _ if b < lower => lower,
+                    _ => b,
+                };
 
-                    Interval::Range(min, max)
-                }
+                Interval::Range(min, max)
             }
         }
     }
@@ -85,10 +92,14 @@ impl Domain for Interval {
 
                 match (a1_val, a2_val) {
                     (Interval::Empty, _) | (_, Interval::Empty) => (Interval::Empty, a2_state),
-                    (Interval::Range(_, b), Interval::Range(c, _)) if b > c => {
+                    // the drawn number is between the least lower bound and the
+                    // greatest upper bound, if any lower bound is below an upper one
+                    (Interval::Range(a, _), Interval::Range(_, d)) if a > d => {
                         (Interval::Empty, a2_state)
                     }
-                    _ => (a1_val.lub(&a2_val), a2_state),
+                    (Interval::Range(a, _), Interval::Range(_, d)) => {
+                        (Interval::Range(a, d), a2_state)
+                    }
                 }
             }
             _ => unreachable!(),
@@ -102,8 +113,7 @@ impl Domain for Interval {
             BooleanExpr::NumNotEq(a1, a2) => Self::eval_aexpr(a2, &Self::eval_aexpr(a1, state).1).1,
 
             BooleanExpr::NumLt(a1, a2) => {
-                let a1_tree = ExpressionTree::build(a1, state).0;
-                let a2_tree = ExpressionTree::build(a2, state).0;
+                let (a1_tree, a2_tree) = ExpressionTree::build_pair(a1, a2, state);
                 let (i1, i2) = (a1_tree.value(), a2_tree.value());
 
                 let l_intersection = i1.glb(&(i2.open_min() - ONE)); // [neginf, b-1]
@@ -116,8 +126,7 @@ impl Domain for Interval {
                         let new_state = a2_tree.refine(r_intersection, new_state);
 
                         let new_state = Self::eval_aexpr(a1, &new_state).1;
-                        let new_state = Self::eval_aexpr(a2, &new_state).1;
-                        new_state
+                        Self::eval_aexpr(a2, &new_state).1
                     }
                 }
             }
@@ -132,6 +141,14 @@ impl Lattice for Interval {
     const UNIT: Self = Interval::Range(ONE, ONE);
     const ZERO: Self = Interval::Range(ZERO, ZERO);
 
+    fn leq(&self, other: &Self) -> bool {
+        match (*self, *other) {
+            (Interval::Empty, _) => true,
+            (_, Interval::Empty) => false,
+            (Interval::Range(a, b), Interval::Range(c, d)) => c <= a && b <= d,
+        }
+    }
+
     fn lub(&self, other: &Self) -> Self {
         match (*self, *other) {
             (a, Interval::Empty) => a,
@@ -157,14 +174,29 @@ impl Lattice for Interval {
     }
 
     fn widen(&self, other: &Self) -> Self {
-        match unsafe { (LOWER_BOUND, UPPER_BOUND) } {
-            (Integer::Value(_), Integer::Value(_)) => other.clone(),
+        match (LOWER_BOUND.get(), UPPER_BOUND.get()) {
+            (Integer::Value(_), Integer::Value(_)) => *other,
             _ => match (*self, *other) {
                 (a, Interval::Empty) => a,
                 (Interval::Empty, b) => b,
                 (Interval::Range(a, b), Interval::Range(c, d)) => {
-                    let min = if a <= c { a } else { Integer::NegInf };
-                    let max = if b >= d { b } else { Integer::PosInf };
+                    let thresholds = WIDENING_THRESHOLDS.with_borrow(|t| t.clone());
+                    let min = match a <= c {
+                        true => a,
+                        _ => thresholds
+                            .iter()
+                            .filter(|t| **t <= c)
+                            .max()
+                            .map_or(Integer::NegInf, |t| *t),
+                    };
+                    let max = match b >= d {
+                        true => b,
+                        _ => thresholds
+                            .iter()
+                            .filter(|t| **t >= d)
+                            .min()
+                            .map_or(Integer::PosInf, |t| *t),
+                    };
                     Interval::Range(min, max)
                 }
             },
@@ -211,12 +243,12 @@ impl FromStr for Interval {
 
                 match parts.len() {
                     1 => {
-                        let val = Integer::from_str(parts[0]).unwrap();
+                        let val = Integer::from_str(parts[0].trim())?;
                         Ok(Interval::Range(val, val))
                     }
                     2 => {
-                        let min = Integer::from_str(parts[0]).unwrap();
-                        let max = Integer::from_str(parts[1]).unwrap();
+                        let min = Integer::from_str(parts[0].trim())?;
+                        let max = Integer::from_str(parts[1].trim())?;
                         Ok(Interval::Range(min, max))
                     }
                     _ => Err("Invalid interval format, more than 2 arguments".into()),
@@ -237,12 +269,33 @@ impl fmt::Display for Interval {
     }
 }
 
+// `{"lo": a, "hi": b}`, the empty interval as `{"bottom": true}`
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Interval::Empty => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("bottom", &true)?;
+                map.end()
+            }
+            Interval::Range(a, b) => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("lo", a)?;
+                map.serialize_entry("hi", b)?;
+                map.end()
+            }
+        }
+    }
+}
+
 impl ops::Add<Interval> for Interval {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
         match (self, other) {
-            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(a + c, b + d),
+            (Interval::Range(a, b), Interval::Range(c, d)) => {
+                Interval::Range(sum(a, c, Integer::NegInf), sum(b, d, Integer::PosInf))
+            }
             _ => Interval::Empty,
         }
         .check_bounds()
@@ -254,7 +307,10 @@ impl ops::Sub<Interval> for Interval {
 
     fn sub(self, other: Self) -> Self {
         match (self, other) {
-            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(a - d, b - c),
+            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(
+                difference(a, d, Integer::NegInf),
+                difference(b, c, Integer::PosInf),
+            ),
             _ => Interval::Empty,
         }
         .check_bounds()
@@ -332,3 +388,20 @@ impl ops::Div<Integer> for Interval {
         self / Interval::Range(other, other)
     }
 }
+
+// the sum of two bounds, `undefined` when they are infinities of opposite signs:
+// the values of such a bound overflowed, and any of them is in the interval
+fn sum(a: Integer, b: Integer, undefined: Integer) -> Integer {
+    match (a, b) {
+        (Integer::PosInf, Integer::NegInf) | (Integer::NegInf, Integer::PosInf) => undefined,
+        _ => a + b,
+    }
+}
+
+// the difference of two bounds, `undefined` when they are the same infinity
+fn difference(a: Integer, b: Integer, undefined: Integer) -> Integer {
+    match (a, b) {
+        (Integer::PosInf, Integer::PosInf) | (Integer::NegInf, Integer::NegInf) => undefined,
+        _ => a - b,
+    }
+}
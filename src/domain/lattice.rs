@@ -1,13 +1,23 @@
-pub trait Lattice {
+pub trait Lattice: Sized {
     const TOP: Self;
     const BOT: Self;
     const UNIT: Self;
     const ZERO: Self;
 
+    // the partial order, of which `lub` and `glb` are the join and the meet
+    fn leq(&self, other: &Self) -> bool;
     fn lub(&self, other: &Self) -> Self;
     fn glb(&self, other: &Self) -> Self;
     fn widen(&self, other: &Self) -> Self;
     fn narrow(&self, other: &Self) -> Self;
 
     fn round(x: &Self) -> Self;
+
+    fn is_top(&self) -> bool {
+        Self::TOP.leq(self)
+    }
+
+    fn is_bottom(&self) -> bool {
+        self.leq(&Self::BOT)
+    }
 }
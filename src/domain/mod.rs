@@ -1,4 +1,5 @@
 pub mod constant;
+#[allow(clippy::module_inception)]
 pub mod domain;
 pub mod expression_tree;
 pub mod interval;
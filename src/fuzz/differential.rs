@@ -0,0 +1,223 @@
+use crate::abstract_semantics;
+use crate::analysis::*;
+use crate::concrete_semantics;
+use crate::concrete_semantics::state::StateOperations;
+use crate::domain::domain::*;
+use crate::domain::lattice::*;
+use crate::parser::ast::*;
+use crate::parser::format::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+use crate::types::integer::*;
+use crate::utils::panic::*;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+// what the concrete semantics make of a program
+pub type Outcome = Result<Option<Execution>, Spanned<ArithmeticExprError>>;
+
+// a disagreement between the semantics of a generated program, or a crash of one
+#[derive(Debug, Clone)]
+pub enum Finding {
+    // printing the program gave a source that does not parse
+    Unparsable(Spanned<SyntaxError>),
+    // the concrete semantics or the analysis of a domain panicked
+    Panic {
+        semantics: &'static str,
+        message: String,
+    },
+    // a concrete state reaches a point, or the exit when `point` is `None`, outside
+    // of the invariant of a domain there
+    Unsound {
+        domain: &'static str,
+        point: Option<Box<ProgramPoint>>,
+        state: String,
+        invariant: String,
+    },
+    // the program fails at run time where a domain has no alarm
+    MissedAlarm {
+        domain: &'static str,
+        error: Spanned<ArithmeticExprError>,
+    },
+}
+
+// prints a generated program and parses it back, to report findings with the
+// source and the places where a reader finds them
+pub fn print_program(
+    program: &Spanned<Statement>,
+) -> Result<(String, Spanned<Statement>), Finding> {
+    let unparsable = |errors: Vec<Spanned<SyntaxError>>| Finding::Unparsable(errors[0].clone());
+
+    let source = format_source(&program.to_string()).map_err(unparsable)?;
+    let program = parse(&source).map_err(unparsable)?;
+    Ok((source, program))
+}
+
+// runs the program with the concrete semantics, drawing from `[a, b]` with `seed`
+pub fn run_concrete(
+    program: &Spanned<Statement>,
+    seed: u64,
+    options: &Options,
+) -> Result<Outcome, Finding> {
+    seed_random(seed);
+    panic::catch_unwind(AssertUnwindSafe(|| evaluate(program, options))).map_err(|payload| {
+        Finding::Panic {
+            semantics: "concrete",
+            message: panic_message(payload),
+        }
+    })
+}
+
+// analyzes the program in the domain `T` and checks the result against `outcome`:
+// every concrete state is in the invariant of its point, and every run-time
+// error is at an alarm for it
+pub fn compare<T: Domain>(
+    domain: &'static str,
+    program: &Spanned<Statement>,
+    outcome: &Outcome,
+    options: &Options,
+) -> Vec<Finding> {
+    let analysis = match panic::catch_unwind(AssertUnwindSafe(|| analyze::<T>(program, options))) {
+        Ok(analysis) => analysis,
+        Err(payload) => {
+            return vec![Finding::Panic {
+                semantics: domain,
+                message: panic_message(payload),
+            }]
+        }
+    };
+
+    let unsound = |point: Option<&ProgramPoint>,
+                   state: &concrete_semantics::state::State,
+                   invariant: String| {
+        Finding::Unsound {
+            domain,
+            point: point.cloned().map(Box::new),
+            state: state.pretty(),
+            invariant,
+        }
+    };
+
+    match outcome {
+        Ok(Some(execution)) => {
+            let mut findings = vec![];
+            for (concrete, invariant) in execution.points.iter().zip(&analysis.points) {
+                // a single finding for each point, the first state it misses
+                if let Some(state) = concrete
+                    .states
+                    .iter()
+                    .find(|s| !contains(&invariant.invariant, s))
+                {
+                    let invariant = invariant.invariant.to_string();
+                    findings.push(unsound(Some(&concrete.point), state, invariant));
+                }
+            }
+            if !contains(&analysis.exit_state, &execution.final_state) {
+                let invariant = analysis.exit_state.to_string();
+                findings.push(unsound(None, &execution.final_state, invariant));
+            }
+            findings
+        }
+        Ok(None) => vec![],
+        Err(error) => {
+            let Some(rule) = rule(&error.node) else {
+                return vec![];
+            };
+            match analysis
+                .alarms
+                .iter()
+                .any(|a| a.rule == rule && overlap(&a.span, &error.span))
+            {
+                true => vec![],
+                _ => vec![Finding::MissedAlarm {
+                    domain,
+                    error: error.clone(),
+                }],
+            }
+        }
+    }
+}
+
+// --- helpers
+
+fn contains<T: Domain>(
+    invariant: &abstract_semantics::state::State<T>,
+    state: &concrete_semantics::state::State,
+) -> bool {
+    !invariant.is_bottom()
+        && state
+            .iter()
+            .all(|(var, val)| alpha::<T>(*val).leq(&invariant.read(var)))
+}
+
+// the abstraction of a single number
+fn alpha<T: Domain>(n: Integer) -> T {
+    let empty = abstract_semantics::state::State::new();
+    T::eval_aexpr(&ArithmeticExpr::Number(n), &empty).0
+}
+
+// the alarm rule of the check guarding against `error`, none for an overflow as
+// the domains do not check for them
+fn rule(error: &ArithmeticExprError) -> Option<&'static str> {
+    match error {
+        ArithmeticExprError::DivByZero => Some("division-by-zero"),
+        ArithmeticExprError::InvalidIntervalBounds => Some("invalid-interval-bounds"),
+        ArithmeticExprError::VariableNotFound => Some("undefined-variable"),
+        ArithmeticExprError::Overflow => None,
+    }
+}
+
+// whether two places share some text, the semantics and the checks locating an
+// error at the expression or at its whole statement
+fn overlap(s1: &Span, s2: &Span) -> bool {
+    s1.start < s2.end && s2.start < s1.end
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |span: &Span| format!("{}:{}", span.line, span.column);
+
+        match self {
+            Finding::Unparsable(error) => write!(
+                f,
+                "{} the printed program does not parse back: {}",
+                at(&error.span),
+                error
+            ),
+            Finding::Panic { semantics, message } => {
+                write!(f, "the {} semantics panicked: {}", semantics, message)
+            }
+            Finding::Unsound {
+                domain,
+                point: Some(point),
+                state,
+                invariant,
+            } => write!(
+                f,
+                "{} {}: the state {{{}}} is not in the {} invariant {}",
+                at(&point.span()),
+                point,
+                state,
+                domain,
+                invariant
+            ),
+            Finding::Unsound {
+                domain,
+                point: None,
+                state,
+                invariant,
+            } => write!(
+                f,
+                "exit: the final state {{{}}} is not in the {} invariant {}",
+                state, domain, invariant
+            ),
+            Finding::MissedAlarm { domain, error } => write!(
+                f,
+                "{} the program fails with `{}`, where the {} domain has no alarm",
+                at(&error.span),
+                error,
+                domain
+            ),
+        }
+    }
+}
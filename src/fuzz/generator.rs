@@ -0,0 +1,392 @@
+use crate::parser::ast::*;
+use crate::parser::span::*;
+use crate::types::integer::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// the most times a generated loop runs its body
+const MAX_ITERATIONS: i64 = 4;
+// the largest magnitude of a generated number
+const MAX_LITERAL: i64 = 10;
+// the most operators nested in a generated expression
+const MAX_EXPR_DEPTH: usize = 3;
+
+// the shape of the generated programs
+#[derive(Debug, Clone, Copy)]
+pub struct GeneratorOptions {
+    // the most statements of a program, not counting the ones driving its loops
+    pub size: usize,
+    // the most loops nested in one another
+    pub depth: usize,
+    // the number of variables besides the loop counters
+    pub variables: usize,
+    // whether expressions may draw numbers with `[a, b]`
+    pub intervals: bool,
+    // whether expressions may divide
+    pub division: bool,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            size: 20,
+            depth: 2,
+            variables: 3,
+            intervals: true,
+            division: true,
+        }
+    }
+}
+
+// a random program that reads only assigned variables and whose loops run at
+// most `MAX_ITERATIONS` times, the same one for the same seed; it may still
+// divide by zero or draw from `[a, b]` with `a > b` at run time
+pub fn generate(options: &GeneratorOptions, seed: u64) -> Spanned<Statement> {
+    let mut generator = Generator {
+        rng: ChaCha8Rng::seed_from_u64(seed),
+        options: *options,
+        budget: options.size.max(1),
+        assigned: vec![],
+        counters: vec![],
+    };
+
+    let mut program = generator.stmt();
+    while generator.budget > 0 {
+        program = chain(program, generator.stmt());
+    }
+    program
+}
+
+struct Generator {
+    rng: ChaCha8Rng,
+    options: GeneratorOptions,
+    // the statements left to generate
+    budget: usize,
+    // the variables assigned on every path to the statement being generated
+    assigned: Vec<Identifier>,
+    // the counters of the loops around it, read but never written by their bodies
+    counters: Vec<Identifier>,
+}
+
+impl Generator {
+    // --- statements
+
+    fn stmt(&mut self) -> Spanned<Statement> {
+        self.budget = self.budget.saturating_sub(1);
+        let nested = self.budget >= 2;
+        let loops = nested && self.counters.len() < self.options.depth;
+
+        match self.rng.gen_range(0..10) {
+            0 | 1 if !self.assigned.is_empty() => self.update(),
+            2 if nested => self.if_stmt(),
+            3 if loops => self.while_stmt(),
+            4 if loops => self.repeat_stmt(),
+            5 if loops => self.for_stmt(),
+            _ => {
+                let var = self.variable();
+                let val = self.aexpr(MAX_EXPR_DEPTH);
+                self.assign(&var);
+                node(Statement::Assignment {
+                    var,
+                    val: Box::new(val),
+                })
+            }
+        }
+    }
+
+    // a short sequence of statements, as the body of an `if` or a loop
+    fn block(&mut self) -> Spanned<Statement> {
+        let mut block = self.stmt();
+        for _ in 0..self.rng.gen_range(0..3) {
+            if self.budget == 0 {
+                break;
+            }
+            block = chain(block, self.stmt());
+        }
+        block
+    }
+
+    // `x op= a`, `x++` or `x--` on an assigned variable
+    fn update(&mut self) -> Spanned<Statement> {
+        let var = self.assigned[self.rng.gen_range(0..self.assigned.len())].clone();
+        match self.rng.gen_range(0..4) {
+            0 => node(Statement::Increment(var)),
+            1 => node(Statement::Decrement(var)),
+            _ => {
+                let op = self.operator();
+                let val = match op {
+                    ArithmeticOp::Div => self.divisor(MAX_EXPR_DEPTH - 1),
+                    _ => self.aexpr(MAX_EXPR_DEPTH - 1),
+                };
+                node(Statement::CompoundAssignment {
+                    var,
+                    op,
+                    val: Box::new(val),
+                })
+            }
+        }
+    }
+
+    fn if_stmt(&mut self) -> Spanned<Statement> {
+        let cond = self.bexpr(MAX_EXPR_DEPTH - 1);
+        let before = self.assigned.clone();
+
+        let s1 = self.block();
+        let after_s1 = std::mem::replace(&mut self.assigned, before);
+        let s2 = match self.rng.gen_bool(0.5) {
+            true => self.block(),
+            _ => node(Statement::Skip),
+        };
+        self.assigned.retain(|var| after_s1.contains(var));
+
+        node(Statement::If {
+            cond: Box::new(cond),
+            s1: Box::new(s1),
+            s2: Box::new(s2),
+        })
+    }
+
+    // `i := 0; while i < n && b do body; i++ done`
+    fn while_stmt(&mut self) -> Spanned<Statement> {
+        let counter = self.counter();
+        let mut cond = compare(BooleanExpr::NumLt, variable(&counter), self.iterations());
+        if self.rng.gen_bool(0.3) {
+            let extra = self.bexpr(1);
+            cond = node(BooleanExpr::And(Box::new(cond), Box::new(extra)));
+        }
+
+        // the body may not run, what it assigns is not assigned after the loop
+        let before = self.assigned.clone();
+        let body = self.loop_body(&counter);
+        self.assigned = before;
+
+        chain(
+            node(Statement::Assignment {
+                var: counter,
+                val: Box::new(number(0)),
+            }),
+            node(Statement::While {
+                cond: Box::new(cond),
+                body: Box::new(body),
+                delay: None,
+            }),
+        )
+    }
+
+    // `i := 0; repeat body; i++ until i >= n || b`
+    fn repeat_stmt(&mut self) -> Spanned<Statement> {
+        let counter = self.counter();
+        let body = self.loop_body(&counter);
+
+        let mut cond = compare(BooleanExpr::NumGtEq, variable(&counter), self.iterations());
+        if self.rng.gen_bool(0.3) {
+            let extra = self.bexpr(1);
+            cond = node(BooleanExpr::Or(Box::new(cond), Box::new(extra)));
+        }
+
+        chain(
+            node(Statement::Assignment {
+                var: counter,
+                val: Box::new(number(0)),
+            }),
+            node(Statement::RepeatUntil {
+                body: Box::new(body),
+                cond: Box::new(cond),
+                delay: None,
+            }),
+        )
+    }
+
+    // `for i in [a, b] do body done` with `b - a < MAX_ITERATIONS`
+    fn for_stmt(&mut self) -> Spanned<Statement> {
+        let var = self.counter();
+        let from = self.rng.gen_range(-2..=2);
+        let to = from + self.rng.gen_range(-1..MAX_ITERATIONS);
+
+        let before = self.assigned.clone();
+        self.counters.push(var.clone());
+        let body = self.block();
+        self.counters.pop();
+        self.assigned = before;
+
+        node(Statement::For {
+            var,
+            from: Box::new(number(from)),
+            to: Box::new(number(to)),
+            body: Box::new(body),
+            delay: None,
+        })
+    }
+
+    // the body of a while or repeat loop, which counts its iterations last
+    fn loop_body(&mut self, counter: &Identifier) -> Spanned<Statement> {
+        self.counters.push(counter.clone());
+        let body = self.block();
+        self.counters.pop();
+        chain(body, node(Statement::Increment(counter.clone())))
+    }
+
+    // --- expressions
+
+    fn aexpr(&mut self, depth: usize) -> Spanned<ArithmeticExpr> {
+        let expr = match self.rng.gen_range(0..10) {
+            _ if depth == 0 => return self.leaf(),
+            0..=2 => return self.leaf(),
+            3 => ArithmeticExpr::Add(self.operand(depth), self.operand(depth)),
+            4 => ArithmeticExpr::Sub(self.operand(depth), self.operand(depth)),
+            5 => ArithmeticExpr::Mul(self.operand(depth), self.operand(depth)),
+            6 if self.options.division => {
+                let dividend = self.operand(depth);
+                ArithmeticExpr::Div(dividend, Box::new(self.divisor(depth - 1)))
+            }
+            7 if self.options.intervals => {
+                let (from, to) = self.bounds(depth);
+                ArithmeticExpr::Interval(Box::new(from), Box::new(to))
+            }
+            8 if !self.assigned.is_empty() => {
+                let var = self.assigned[self.rng.gen_range(0..self.assigned.len())].clone();
+                match self.rng.gen_bool(0.5) {
+                    true => ArithmeticExpr::PostIncrement(var),
+                    _ => ArithmeticExpr::PostDecrement(var),
+                }
+            }
+            _ => return self.leaf(),
+        };
+        node(expr)
+    }
+
+    fn operand(&mut self, depth: usize) -> Box<Spanned<ArithmeticExpr>> {
+        Box::new(self.aexpr(depth - 1))
+    }
+
+    // a number or a variable that can be read
+    fn leaf(&mut self) -> Spanned<ArithmeticExpr> {
+        let readable = self.assigned.len() + self.counters.len();
+        match readable > 0 && self.rng.gen_bool(0.6) {
+            true => {
+                let i = self.rng.gen_range(0..readable);
+                let var = self.assigned.iter().chain(&self.counters).nth(i).unwrap();
+                variable(var)
+            }
+            _ => number(self.rng.gen_range(-MAX_LITERAL..=MAX_LITERAL)),
+        }
+    }
+
+    // `[a, a + k]` most of the time, any two expressions otherwise
+    fn bounds(&mut self, depth: usize) -> (Spanned<ArithmeticExpr>, Spanned<ArithmeticExpr>) {
+        match self.rng.gen_bool(0.95) {
+            true => {
+                let from = self.leaf();
+                let width = number(self.rng.gen_range(0..=MAX_LITERAL));
+                let to = node(ArithmeticExpr::Add(Box::new(from.clone()), Box::new(width)));
+                (from, to)
+            }
+            _ => (self.aexpr(depth - 1), self.aexpr(depth - 1)),
+        }
+    }
+
+    // a number other than zero most of the time, any expression otherwise
+    fn divisor(&mut self, depth: usize) -> Spanned<ArithmeticExpr> {
+        match self.rng.gen_bool(0.9) {
+            true => number(self.nonzero()),
+            _ => self.aexpr(depth),
+        }
+    }
+
+    fn bexpr(&mut self, depth: usize) -> Spanned<BooleanExpr> {
+        let expr = match self.rng.gen_range(0..12) {
+            0 => BooleanExpr::True,
+            1 => BooleanExpr::False,
+            2 if depth > 0 => BooleanExpr::Not(Box::new(self.bexpr(depth - 1))),
+            3 if depth > 0 => BooleanExpr::And(
+                Box::new(self.bexpr(depth - 1)),
+                Box::new(self.bexpr(depth - 1)),
+            ),
+            4 if depth > 0 => BooleanExpr::Or(
+                Box::new(self.bexpr(depth - 1)),
+                Box::new(self.bexpr(depth - 1)),
+            ),
+            _ => {
+                let comparisons = [
+                    BooleanExpr::NumEq,
+                    BooleanExpr::NumNotEq,
+                    BooleanExpr::NumLt,
+                    BooleanExpr::NumGt,
+                    BooleanExpr::NumLtEq,
+                    BooleanExpr::NumGtEq,
+                ];
+                let comparison = comparisons[self.rng.gen_range(0..comparisons.len())];
+                let a1 = self.aexpr(MAX_EXPR_DEPTH - 1);
+                let a2 = self.aexpr(MAX_EXPR_DEPTH - 1);
+                return compare(comparison, a1, a2);
+            }
+        };
+        node(expr)
+    }
+
+    // --- names and numbers
+
+    // one of the variables, assigned or not
+    fn variable(&mut self) -> Identifier {
+        format!("x{}", self.rng.gen_range(0..self.options.variables.max(1)))
+    }
+
+    // the counter of a loop nested in the ones being generated
+    fn counter(&self) -> Identifier {
+        format!("i{}", self.counters.len())
+    }
+
+    fn assign(&mut self, var: &Identifier) {
+        if !self.assigned.contains(var) {
+            self.assigned.push(var.clone());
+        }
+    }
+
+    fn operator(&mut self) -> ArithmeticOp {
+        match self.rng.gen_range(0..4) {
+            0 => ArithmeticOp::Add,
+            1 => ArithmeticOp::Sub,
+            2 => ArithmeticOp::Mul,
+            _ if self.options.division => ArithmeticOp::Div,
+            _ => ArithmeticOp::Add,
+        }
+    }
+
+    fn iterations(&mut self) -> Spanned<ArithmeticExpr> {
+        number(self.rng.gen_range(0..=MAX_ITERATIONS))
+    }
+
+    fn nonzero(&mut self) -> i64 {
+        match self.rng.gen_range(1..=MAX_LITERAL) {
+            n if self.rng.gen_bool(0.5) => -n,
+            n => n,
+        }
+    }
+}
+
+// --- nodes
+
+// the generated nodes have no place in any source, they are printed to get one
+fn node<T>(node: T) -> Spanned<T> {
+    Spanned::new(node, Span::default())
+}
+
+fn chain(s1: Spanned<Statement>, s2: Spanned<Statement>) -> Spanned<Statement> {
+    node(Statement::Chain(Box::new(s1), Box::new(s2)))
+}
+
+fn number(n: i64) -> Spanned<ArithmeticExpr> {
+    node(ArithmeticExpr::Number(Integer::Value(n)))
+}
+
+fn variable(var: &Identifier) -> Spanned<ArithmeticExpr> {
+    node(ArithmeticExpr::Variable(var.clone()))
+}
+
+fn compare(
+    comparison: fn(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>) -> BooleanExpr,
+    a1: Spanned<ArithmeticExpr>,
+    a2: Spanned<ArithmeticExpr>,
+) -> Spanned<BooleanExpr> {
+    node(comparison(Box::new(a1), Box::new(a2)))
+}
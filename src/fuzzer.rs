@@ -0,0 +1,129 @@
+use crate::cli::*;
+use crate::config::*;
+use crate::EXIT_FAILURE;
+use crate::{apply_settings, set_bounds};
+use rayon::prelude::*;
+use std::panic;
+use std::process::exit;
+use whilep::analysis::Options;
+use whilep::domain::constant::*;
+use whilep::domain::interval::*;
+use whilep::fuzz::differential::*;
+use whilep::fuzz::generator::*;
+use whilep::parser::ast::STRICT_EVALUATION;
+use whilep::types::integer::*;
+
+// what comparing the semantics of a generated program found
+struct Trial {
+    seed: u64,
+    source: Option<String>,
+    failed: bool,
+    findings: Vec<Finding>,
+}
+
+// generates `count` programs from `seed` on, and compares what the selected
+// domains find about each one with its concrete run
+pub fn run_fuzz(
+    count: u64,
+    options: &GeneratorOptions,
+    domains: &AbstractOptions,
+    settings: &Settings,
+    seed: u64,
+) {
+    // the panics are reported with the program that caused them
+    panic::set_hook(Box::new(|_| {}));
+
+    // the flag is set on this thread only
+    let strict = STRICT_EVALUATION.get();
+    let trials = (0..count)
+        .into_par_iter()
+        .map(|k| {
+            STRICT_EVALUATION.set(strict);
+            fuzz_program(seed.wrapping_add(k), options, domains, settings)
+        })
+        .collect::<Vec<_>>();
+    let _ = panic::take_hook();
+
+    let found = trials
+        .iter()
+        .filter(|t| !t.findings.is_empty())
+        .collect::<Vec<_>>();
+    for trial in &found {
+        println!(
+            "[ERROR] the program of seed {} has {} finding(s), run it alone with `--seed {} --count 1` and the same other options",
+            trial.seed,
+            trial.findings.len(),
+            trial.seed
+        );
+        for finding in &trial.findings {
+            println!("{}", finding);
+        }
+        if let Some(source) = &trial.source {
+            println!("{}", source);
+        }
+    }
+
+    let failed = trials.iter().filter(|t| t.failed).count();
+    info!(
+        "compared {} program(s) from seed {}, {} failed at run time, {} with findings",
+        count,
+        seed,
+        failed,
+        found.len()
+    );
+
+    if !found.is_empty() {
+        exit(EXIT_FAILURE);
+    }
+}
+
+// runs on a thread of the pool, whose settings are those of the previous program
+fn fuzz_program(
+    seed: u64,
+    options: &GeneratorOptions,
+    domains: &AbstractOptions,
+    settings: &Settings,
+) -> Trial {
+    let mut trial = Trial {
+        seed,
+        source: None,
+        failed: false,
+        findings: vec![],
+    };
+
+    let (source, ast) = match print_program(&generate(options, seed)) {
+        Ok(program) => program,
+        Err(finding) => {
+            trial.findings.push(finding);
+            return trial;
+        }
+    };
+    trial.source = Some(source);
+
+    let outcome = match run_concrete(&ast, seed, &Options::current()) {
+        Ok(outcome) => outcome,
+        Err(finding) => {
+            trial.findings.push(finding);
+            return trial;
+        }
+    };
+    trial.failed = outcome.is_err();
+
+    apply_settings(settings, None);
+
+    if domains.selected(DomainName::Interval) {
+        let bounds = domains
+            .bounds
+            .unwrap_or(Bounds::Range(Integer::NegInf, Integer::PosInf));
+        set_bounds(Some(bounds), &ast);
+        let findings = compare::<Interval>("interval", &ast, &outcome, &Options::current());
+        trial.findings.extend(findings);
+    }
+
+    if domains.selected(DomainName::Constant) {
+        let findings = compare::<Constant>("constant", &ast, &outcome, &Options::current());
+        trial.findings.extend(findings);
+    }
+
+    trial
+}
@@ -0,0 +1,14 @@
+pub mod abstract_semantics;
+pub mod analysis;
+pub mod concrete_semantics;
+pub mod domain;
+pub mod fuzz;
+pub mod output;
+pub mod parser;
+pub mod server;
+pub mod types;
+pub mod utils;
+
+pub use abstract_semantics::check::{Alarm, Severity};
+pub use analysis::*;
+pub use parser::ast::{parse, parse_partial};
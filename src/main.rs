@@ -1,33 +1,236 @@
-use crate::domain::constant::*;
-use crate::domain::domain::*;
-use crate::domain::interval::*;
-use crate::parser::ast::*;
-use crate::parser::program_point::*;
-use crate::types::integer::*;
 use cli::*;
+use config::*;
 use std::fs;
-use std::str::FromStr;
+use std::process::exit;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use whilep::abstract_semantics::check::dedup;
+use whilep::concrete_semantics::trace;
+use whilep::domain::constant::*;
+use whilep::domain::domain::*;
+use whilep::domain::interval::*;
+use whilep::domain::lattice::*;
+use whilep::output::annotate::*;
+use whilep::output::dot::*;
+use whilep::output::html;
+use whilep::output::json;
+use whilep::output::sarif;
+use whilep::parser::ast::*;
+use whilep::parser::format::*;
+use whilep::parser::program_point::*;
+use whilep::parser::span::*;
+use whilep::types::integer::*;
+use whilep::*;
 
-mod abstract_semantics;
+// prints an `[INFO]` line, unless `--quiet` is set
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if !$crate::cli::QUIET.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("[INFO] {}", format!($($arg)*));
+        }
+    };
+}
+
+mod batch;
 mod cli;
-mod concrete_semantics;
-mod domain;
-mod parser;
-mod types;
-mod utils;
+mod config;
+mod fuzzer;
+mod repl;
+
+// --- exit codes
+
+// the program is at fault: it does not parse, fails at run time, has error alarms
+// or is not formatted
+const EXIT_FAILURE: i32 = 1;
+// the command could not do its job: a file cannot be read or written, or a server
+// cannot talk to its client (clap exits with 2 as well on invalid arguments)
+const EXIT_USAGE: i32 = 2;
+
+// --- loading
+
+fn read_source(file: &str) -> String {
+    match fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(e) => {
+            println!("[ERROR] failed to read {}: {}", file, e);
+            exit(EXIT_USAGE)
+        }
+    }
+}
+
+// reads and parses `file`, showing the syntax errors with `report` and exiting if
+// it does not parse
+fn load(
+    file: &str,
+    report: impl Fn(&str, &[Spanned<SyntaxError>]),
+) -> (String, Spanned<Statement>) {
+    let source = read_source(file);
+    match parse(&source) {
+        Ok(ast) => (source, ast),
+        Err(errors) => {
+            report(&source, &errors);
+            exit(EXIT_FAILURE)
+        }
+    }
+}
 
-fn run_concrete(ast: &Statement) {
-    use concrete_semantics::denote::*;
-    use concrete_semantics::state::*;
+fn load_for(file: &str, format: Format) -> (String, Spanned<Statement>) {
+    load(file, |source, errors| match format {
+        Format::Json => {
+            let doc = serde_json::json!({
+                "version": json::SCHEMA_VERSION,
+                "file": file,
+                "errors": json::syntax_errors(file, errors),
+            });
+            println!("{}", serde_json::to_string_pretty(&doc).unwrap());
+        }
+        _ => report_syntax_errors(file, source, errors),
+    })
+}
 
-    let induced_function = denote_stmt(ast.clone());
+fn report_syntax_errors(file: &str, source: &str, errors: &[Spanned<SyntaxError>]) {
+    for e in errors {
+        let message = format!("failed to parse the program: {}", e);
+        println!(
+            "[ERROR] {}",
+            render_diagnostic(file, source, &e.span, &message)
+        );
+    }
+    println!("[ERROR] found {} syntax error(s)", errors.len());
+}
+
+// the syntax errors as alarms, for the sarif logs
+fn syntax_alarms(errors: &[Spanned<SyntaxError>]) -> Vec<Alarm> {
+    errors
+        .iter()
+        .map(|e| Alarm {
+            rule: "syntax-error",
+            severity: Severity::Error,
+            message: e.to_string(),
+            span: e.span,
+        })
+        .collect()
+}
+
+// sets the bounds of the interval domain for `ast`, returns them
+fn set_bounds(bounds: Option<Bounds>, ast: &Statement) -> (Integer, Integer) {
+    match bounds {
+        None => (),
+        Some(Bounds::Auto) => {
+            let max = ast
+                .get_max_number()
+                .map(Integer::Value)
+                .unwrap_or(Integer::PosInf);
+
+            LOWER_BOUND.set(-max);
+            UPPER_BOUND.set(max);
+        }
+        Some(Bounds::Range(min, max)) => {
+            LOWER_BOUND.set(min);
+            UPPER_BOUND.set(max);
+        }
+    }
+    (LOWER_BOUND.get(), UPPER_BOUND.get())
+}
+
+// --- pretty printing
+
+fn pretty_states(states: &[whilep::concrete_semantics::state::State]) -> String {
+    use whilep::concrete_semantics::state::*;
+
+    match states.is_empty() {
+        true => "UNREACHED".to_string(),
+        _ => states
+            .iter()
+            .map(|s| format!("{{{}}}", s.pretty()))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+fn pretty_invariant<T: Domain>(state: &whilep::abstract_semantics::state::State<T>) -> String {
+    use whilep::abstract_semantics::state::State;
 
-    println!("[INFO] evaluating the concrete semantics");
-    let state = induced_function(State::new());
     match state {
-        Ok(Some(state)) => {
+        State::Bottom => "unreachable".to_string(),
+        State::Just(vars) if vars.is_empty() => "{ }".to_string(),
+        State::Just(_) => format!("{{ {} }}", state),
+    }
+}
+
+fn report_alarms(file: &str, source: &str, alarms: &[Alarm]) {
+    for alarm in alarms {
+        let tag = match alarm.severity {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+        };
+        println!(
+            "[{}] {}",
+            tag,
+            render_diagnostic(file, source, &alarm.span, &alarm.message)
+        );
+    }
+}
+
+// --- run
+
+fn run(file: &str, format: Format) {
+    let (source, ast) = load_for(file, format);
+
+    if format == Format::Json {
+        let result = evaluate(&ast, &Options::current());
+        let doc = serde_json::json!({
+            "version": json::SCHEMA_VERSION,
+            "file": file,
+            "program": json::program(&ast),
+            "concrete": json::execution(file, &result),
+        });
+        println!("{}", serde_json::to_string_pretty(&doc).unwrap());
+        if !matches!(result, Ok(Some(_))) {
+            exit(EXIT_FAILURE);
+        }
+        return;
+    }
+
+    info!("evaluating the concrete semantics");
+    match evaluate(&ast, &Options::current()) {
+        Ok(Some(execution)) if format == Format::Annotated => {
+            let notes = execution
+                .points
+                .iter()
+                .map(|p| p.states.clone())
+                .collect::<Vec<_>>();
+            let merge =
+                |a: &Vec<_>, b: &Vec<_>| trace::merge(vec![a.clone()], vec![b.clone()])[0].clone();
+            print!("{}", annotate(&ast, &notes, &merge, &|s| pretty_states(s)));
+        }
+        Ok(Some(execution)) => {
+            let headers = vec![
+                "#".to_string(),
+                "Location".to_string(),
+                "Program point".to_string(),
+                "States".to_string(),
+            ];
+            let rows = execution
+                .points
+                .iter()
+                .enumerate()
+                .map(|(i, p)| {
+                    vec![
+                        i.to_string(),
+                        location(file, &p.point.span()),
+                        p.point.to_string(),
+                        pretty_states(&p.states),
+                    ]
+                })
+                .collect::<Vec<_>>();
+
+            draw_table(headers, rows);
+
             let headers = vec!["#".to_string(), "Var".to_string(), "Val".to_string()];
-            let rows = state
+            let mut vars = execution.final_state.iter().collect::<Vec<_>>();
+            vars.sort();
+            let rows = vars
                 .iter()
                 .enumerate()
                 .map(|(i, (k, v))| vec![i.to_string(), k.to_string(), v.to_string()])
@@ -35,86 +238,483 @@ fn run_concrete(ast: &Statement) {
 
             draw_table(headers, rows)
         }
-        Ok(None) => println!("[ERROR] Arithmetic expression error"),
-        Err(e) => println!("[ERROR] {}", e),
+        Ok(None) => {
+            println!("[ERROR] Arithmetic expression error");
+            exit(EXIT_FAILURE);
+        }
+        Err(e) => {
+            println!(
+                "[ERROR] {}",
+                render_diagnostic(file, &source, &e.span, &e.to_string())
+            );
+            exit(EXIT_FAILURE);
+        }
     }
 }
 
-fn run_abstract<T: Domain>(ast: &Statement) {
-    use abstract_semantics::denote::*;
-    use abstract_semantics::state::*;
-
-    let induced_function: StateFunction<T> = denote_stmt(ast.clone());
+// --- analyze
 
-    println!(
-        "[INFO] evaluating the abstract semantics in the {} domain",
-        std::any::type_name::<T>().split("::").last().unwrap()
-    );
-    let (_, inv) = induced_function(State::new());
-    let points = get_program_points(ast.clone());
+fn analyze_domain<T: Domain>(ast: &Spanned<Statement>, file: &str, source: &str, format: Format) {
+    let name = std::any::type_name::<T>().split("::").last().unwrap();
+    info!("evaluating the abstract semantics in the {} domain", name);
+    let analysis = analyze::<T>(ast, &Options::current());
 
-    assert!(inv.len() == points.len());
+    if format == Format::Annotated {
+        let notes = analysis
+            .points
+            .iter()
+            .map(|p| p.invariant.clone())
+            .collect::<Vec<_>>();
+        return print!(
+            "{}",
+            annotate(ast, &notes, &|a, b| a.lub(b), &pretty_invariant)
+        );
+    }
 
     let headers = vec![
         "#".to_string(),
+        "Location".to_string(),
         "Program point".to_string(),
         "Invariant".to_string(),
     ];
 
-    let rows = points
+    let rows = analysis
+        .points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            vec![
+                i.to_string(),
+                location(file, &p.point.span()),
+                p.point.to_string(),
+                p.invariant.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    draw_table(headers, rows);
+    report_alarms(file, source, &analysis.alarms);
+}
+
+fn run_analyze(file: &str, domains: &AbstractOptions, format: Format) {
+    let (source, ast) = load_for(file, format);
+
+    if format == Format::Json {
+        let mut doc = serde_json::json!({
+            "version": json::SCHEMA_VERSION,
+            "file": file,
+            "program": json::program(&ast),
+        });
+
+        if domains.selected(DomainName::Interval) {
+            let bounds = set_bounds(domains.bounds, &ast);
+            doc["abstract"]["interval"] =
+                json::analysis(file, &analyze::<Interval>(&ast, &Options::current()));
+            doc["abstract"]["interval"]["bounds"] = serde_json::json!([bounds.0, bounds.1]);
+        }
+
+        if domains.selected(DomainName::Constant) {
+            doc["abstract"]["constant"] =
+                json::analysis(file, &analyze::<Constant>(&ast, &Options::current()));
+        }
+
+        return println!("{}", serde_json::to_string_pretty(&doc).unwrap());
+    }
+
+    if domains.selected(DomainName::Interval) {
+        let (lower, upper) = set_bounds(domains.bounds, &ast);
+        info!("using interval bounds: [{}, {}]", lower, upper);
+        analyze_domain::<Interval>(&ast, file, &source, format);
+    }
+
+    if domains.selected(DomainName::Constant) {
+        analyze_domain::<Constant>(&ast, file, &source, format);
+    }
+}
+
+// --- check
+
+fn run_check(file: &str, domains: &AbstractOptions, format: CheckFormat) {
+    let (source, ast) = load(file, |source, errors| match format {
+        CheckFormat::Sarif => {
+            let log = sarif::log(file, source, &syntax_alarms(errors));
+            println!("{}", serde_json::to_string_pretty(&log).unwrap());
+        }
+        _ => report_syntax_errors(file, source, errors),
+    });
+
+    let mut alarms = vec![];
+
+    if domains.selected(DomainName::Interval) {
+        set_bounds(domains.bounds, &ast);
+        alarms.extend(analyze::<Interval>(&ast, &Options::current()).alarms);
+    }
+
+    if domains.selected(DomainName::Constant) {
+        alarms.extend(analyze::<Constant>(&ast, &Options::current()).alarms);
+    }
+
+    let alarms = dedup(alarms);
+    match format {
+        CheckFormat::Sarif => {
+            let log = sarif::log(file, &source, &alarms);
+            println!("{}", serde_json::to_string_pretty(&log).unwrap());
+        }
+        CheckFormat::Text => {
+            report_alarms(file, &source, &alarms);
+            info!("found {} alarm(s) in {}", alarms.len(), file);
+        }
+    }
+
+    if alarms.iter().any(|a| a.severity == Severity::Error) {
+        exit(EXIT_FAILURE);
+    }
+}
+
+// --- points
+
+fn run_points(file: &str) {
+    let (_, ast) = load_for(file, Format::Table);
+
+    let headers = vec![
+        "#".to_string(),
+        "Location".to_string(),
+        "Kind".to_string(),
+        "Program point".to_string(),
+    ];
+    let rows = get_program_points(ast)
         .iter()
-        .zip(inv.iter())
         .enumerate()
-        .map(|(i, (p, s))| vec![i.to_string(), p.to_string(), s.to_string()])
+        .map(|(i, p)| {
+            vec![
+                i.to_string(),
+                location(file, &p.span()),
+                p.kind().to_string(),
+                p.to_string(),
+            ]
+        })
         .collect::<Vec<_>>();
 
-    draw_table(headers, rows)
+    draw_table(headers, rows);
+}
+
+// --- dot
+
+fn dot_domain<T: Domain>(ast: &Spanned<Statement>) {
+    let name = std::any::type_name::<T>().split("::").last().unwrap();
+    let notes = analyze::<T>(ast, &Options::current())
+        .points
+        .iter()
+        .map(|p| p.invariant.clone())
+        .collect::<Vec<_>>();
+    print!("{}", cfg(name, ast, &notes, &pretty_invariant));
+}
+
+fn run_dot(file: &str, domains: &[DomainName], bounds: Option<Bounds>) {
+    let (source, ast) = load_for(file, Format::Table);
+
+    for domain in domains {
+        match domain {
+            DomainName::Interval => {
+                set_bounds(bounds, &ast);
+                dot_domain::<Interval>(&ast);
+            }
+            DomainName::Constant => dot_domain::<Constant>(&ast),
+        }
+    }
+
+    if !domains.is_empty() {
+        return;
+    }
+
+    match evaluate(&ast, &Options::current()) {
+        Ok(Some(execution)) => {
+            let notes = execution
+                .points
+                .iter()
+                .map(|p| p.states.clone())
+                .collect::<Vec<_>>();
+            print!("{}", cfg("Concrete", &ast, &notes, &|s| pretty_states(s)));
+        }
+        Ok(None) => {
+            println!("[ERROR] Arithmetic expression error");
+            exit(EXIT_FAILURE);
+        }
+        Err(e) => {
+            println!(
+                "[ERROR] {}",
+                render_diagnostic(file, &source, &e.span, &e.to_string())
+            );
+            exit(EXIT_FAILURE);
+        }
+    }
+}
+
+// --- report
+
+fn abstract_column<T: Domain>(name: &str) -> impl Fn(&Spanned<Statement>) -> html::Column + '_ {
+    move |ast| {
+        let start = Instant::now();
+        let analysis = analyze::<T>(ast, &Options::current());
+        let elapsed = start.elapsed();
+
+        html::Column {
+            name: name.to_string(),
+            notes: analysis
+                .points
+                .iter()
+                .map(|p| pretty_invariant(&p.invariant))
+                .collect(),
+            unreachable: analysis
+                .points
+                .iter()
+                .filter(|p| p.invariant == whilep::abstract_semantics::state::State::Bottom)
+                .count(),
+            alarms: analysis.alarms,
+            elapsed,
+        }
+    }
+}
+
+fn run_report(file: &str, out: &str, domains: &AbstractOptions) {
+    let (source, ast) = load_for(file, Format::Table);
+    let mut columns = vec![];
+
+    if domains.selected(DomainName::Interval) {
+        set_bounds(domains.bounds, &ast);
+        columns.push(abstract_column::<Interval>("Interval")(&ast));
+    }
+
+    if domains.selected(DomainName::Constant) {
+        columns.push(abstract_column::<Constant>("Constant")(&ast));
+    }
+
+    let start = Instant::now();
+    let result = evaluate(&ast, &Options::current());
+    let elapsed = start.elapsed();
+
+    let final_state = match result {
+        Ok(Some(execution)) => {
+            columns.push(html::Column {
+                name: "Concrete".to_string(),
+                notes: execution
+                    .points
+                    .iter()
+                    .map(|p| pretty_states(&p.states))
+                    .collect(),
+                unreachable: execution
+                    .points
+                    .iter()
+                    .filter(|p| p.states.is_empty())
+                    .count(),
+                alarms: vec![],
+                elapsed,
+            });
+            let mut vars = execution
+                .final_state
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_string()))
+                .collect::<Vec<_>>();
+            vars.sort();
+            Ok(vars)
+        }
+        Ok(None) => Err("Arithmetic expression error".to_string()),
+        Err(e) => Err(format!("{}: {}", location(file, &e.span), e)),
+    };
+
+    let report = html::Report {
+        file,
+        source: &source,
+        ast: &ast,
+        columns,
+        final_state: Some(final_state),
+    };
+    if let Err(e) = fs::write(out, html::render(&report)) {
+        println!("[ERROR] failed to write {}: {}", out, e);
+        exit(EXIT_USAGE);
+    }
+    info!("report written to {}", out);
 }
 
-fn set_min_max_interval(opts: &ProgramOptions, ast: &Statement) {
-    unsafe {
-        match opts.bounds.clone() {
-            None => (),
-            Some(b) if b == "auto" => {
-                let max = ast
-                    .get_max_number()
-                    .map(|n| Integer::Value(n))
-                    .unwrap_or(Integer::PosInf);
+// --- fmt
+
+fn run_fmt(files: &[String], check: bool) {
+    let mut failed = 0;
 
-                LOWER_BOUND = -max;
-                UPPER_BOUND = max;
+    for file in files {
+        let source = read_source(file);
+        let formatted = match format_source(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                report_syntax_errors(file, &source, &errors);
+                failed += 1;
+                continue;
             }
-            Some(b) => match Interval::from_str(&b).unwrap() {
-                Interval::Empty => unreachable!(),
-                Interval::Range(min, max) if min <= max => {
-                    LOWER_BOUND = min;
-                    UPPER_BOUND = max;
+        };
+
+        match (formatted == source, check) {
+            (true, _) => (),
+            (false, true) => {
+                println!("[ERROR] {} is not formatted", file);
+                failed += 1;
+            }
+            _ => {
+                if let Err(e) = fs::write(file, formatted) {
+                    println!("[ERROR] failed to write {}: {}", file, e);
+                    exit(EXIT_USAGE);
                 }
-                _ => panic!("[ERROR] invalid bounds: min > max "),
-            },
+            }
         }
-        println!(
-            "[INFO] using interval bounds: [{}, {}]",
-            LOWER_BOUND, UPPER_BOUND
-        );
+    }
+
+    if failed > 0 {
+        exit(EXIT_FAILURE);
     }
 }
 
-fn main() {
-    let opts = cli::parse_options();
-    let source = fs::read_to_string(&opts.source_file).expect("[ERROR] failed to read the source");
-    let ast = parse(&source).expect("[ERROR] failed to parse the program");
+// --- servers
+
+fn run_lsp() {
+    let stdin = std::io::stdin().lock();
+    let shutdown = match whilep::server::lsp::serve(stdin, std::io::stdout().lock()) {
+        Ok(shutdown) => shutdown,
+        Err(e) => {
+            println!("[ERROR] failed to talk to the client: {}", e);
+            exit(EXIT_USAGE);
+        }
+    };
 
-    if opts.check_interval {
-        set_min_max_interval(&opts, &ast);
-        run_abstract::<Interval>(&ast);
+    // an exit without a shutdown request first is an error, as the protocol says
+    if !shutdown {
+        exit(EXIT_FAILURE);
     }
+}
 
-    if opts.check_constant {
-        run_abstract::<Constant>(&ast);
+fn run_dap() {
+    let stdin = std::io::BufReader::new(std::io::stdin());
+    if let Err(e) = whilep::server::dap::serve(stdin, std::io::stdout().lock()) {
+        println!("[ERROR] failed to talk to the client: {}", e);
+        exit(EXIT_USAGE);
     }
+}
 
-    if opts.eval {
-        run_concrete(&ast);
+fn run_serve(port: u16, max_steps: usize, timeout: u64, seed: Option<u64>) {
+    let listener = match std::net::TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!("[ERROR] failed to listen on port {}: {}", port, e);
+            exit(EXIT_USAGE);
+        }
+    };
+    info!("listening on http://127.0.0.1:{}", port);
+
+    // the settings are those of this thread, the analyses run on others
+    let config = whilep::server::http::Config {
+        limits: whilep::server::http::Limits {
+            steps: max_steps,
+            timeout: std::time::Duration::from_millis(timeout),
+        },
+        options: Options::current(),
+        seed,
+    };
+    if let Err(e) = whilep::server::http::serve(&listener, &config) {
+        println!("[ERROR] failed to accept a connection: {}", e);
+        exit(EXIT_USAGE);
+    }
+}
+
+// the settings shared by the commands, set before any of them runs
+fn apply_settings(settings: &Settings, seed: Option<u64>) {
+    let thresholds = settings.widening_thresholds.clone().unwrap_or_default();
+    WIDENING_DELAY.set(settings.widening_delay.map(i64::from));
+    WIDENING_THRESHOLDS.set(thresholds.into_iter().map(Integer::Value).collect());
+    if let Some(seed) = seed.or(settings.seed) {
+        seed_random(seed);
+    }
+}
+
+fn main() {
+    let opts = cli::parse_options();
+
+    QUIET.store(opts.quiet, Ordering::Relaxed);
+    STRICT_EVALUATION.set(opts.strict);
+
+    // the flags take precedence over the overrides of the file, which take
+    // precedence over the defaults of the project
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("[ERROR] failed to read {}: {}", CONFIG_FILE, e);
+            exit(EXIT_USAGE);
+        }
+    };
+    let settings = config.settings(opts.command.file());
+    apply_settings(&settings, opts.seed);
+
+    match &opts.command {
+        Command::Run { file, format } => {
+            run(file, format.or(settings.format).unwrap_or(Format::Table))
+        }
+        Command::Analyze {
+            file,
+            domains,
+            format,
+        } => run_analyze(
+            file,
+            &domains.or(&settings),
+            format.or(settings.format).unwrap_or(Format::Table),
+        ),
+        Command::Check {
+            file,
+            domains,
+            format,
+        } => match std::path::Path::new(file).is_dir() {
+            true => batch::run_check_dir(file, domains, *format, &config, opts.seed),
+            _ => run_check(file, &domains.or(&settings), *format),
+        },
+        Command::Points { file } => run_points(file),
+        Command::Dot {
+            file,
+            domains,
+            bounds,
+        } => run_dot(file, domains, bounds.or(settings.bounds)),
+        Command::Report {
+            file,
+            output,
+            domains,
+        } => run_report(file, output, &domains.or(&settings)),
+        Command::Fmt { files, check } => run_fmt(files, *check),
+        Command::Fuzz {
+            count,
+            size,
+            depth,
+            variables,
+            no_intervals,
+            no_division,
+            domains,
+        } => {
+            let options = whilep::fuzz::generator::GeneratorOptions {
+                size: *size,
+                depth: *depth,
+                variables: *variables,
+                intervals: !no_intervals,
+                division: !no_division,
+            };
+            let seed = opts.seed.or(settings.seed).unwrap_or_else(rand::random);
+            fuzzer::run_fuzz(*count, &options, &domains.or(&settings), &settings, seed)
+        }
+        Command::Lsp => run_lsp(),
+        Command::Dap => run_dap(),
+        Command::Repl { bounds } => repl::run_repl(bounds.or(settings.bounds)),
+        Command::Serve {
+            port,
+            max_steps,
+            timeout,
+        } => run_serve(
+            *port,
+            max_steps.or(settings.max_steps).unwrap_or(1_000_000),
+            timeout.or(settings.timeout).unwrap_or(5000),
+            opts.seed.or(settings.seed),
+        ),
     }
 }
@@ -0,0 +1,185 @@
+use crate::parser::ast::*;
+use crate::parser::format::pretty_delay;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+use std::collections::VecDeque;
+
+const INDENT: &str = "    ";
+
+// a printed line and the note commented after it
+struct Line {
+    text: String,
+    note: Option<String>,
+}
+
+struct Annotator<'a, S> {
+    notes: VecDeque<S>,
+    merge: &'a dyn Fn(&S, &S) -> S,
+    show: &'a dyn Fn(&S) -> String,
+    lines: Vec<Line>,
+    depth: usize,
+}
+
+// prints the program one statement per line with `notes[i]`, the state at the i-th
+// program point, commented next to it; `merge` joins the two states of a repeat body
+pub fn annotate<S: Clone>(
+    ast: &Spanned<Statement>,
+    notes: &[S],
+    merge: &dyn Fn(&S, &S) -> S,
+    show: &dyn Fn(&S) -> String,
+) -> String {
+    let mut annotator = Annotator {
+        notes: notes.iter().cloned().collect(),
+        merge,
+        show,
+        lines: vec![],
+        depth: 0,
+    };
+    annotator.stmt(ast);
+
+    annotator
+        .lines
+        .iter()
+        .map(|line| match &line.note {
+            Some(note) => format!("{} /* {} */", line.text, note),
+            None => line.text.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+impl<'a, S: Clone> Annotator<'a, S> {
+    fn stmt(&mut self, stmt: &Spanned<Statement>) {
+        match &stmt.node {
+            Statement::Error(_) => (),
+
+            Statement::Chain(s1, s2) => {
+                self.stmt(s1);
+                if let Some(last) = self.lines.last_mut() {
+                    last.text.push(';');
+                }
+                self.stmt(s2);
+            }
+
+            Statement::If { cond, s1, s2 } => {
+                self.push(format!("if {} then", cond));
+                self.block(s1, "if-guard");
+                self.push("else".to_string());
+                self.block(s2, "else-guard");
+                self.push("end".to_string());
+                self.trailing("end-if");
+            }
+
+            Statement::While { cond, body, delay } => {
+                self.own_line("while-inv");
+                self.push(format!("while {}{} do", cond, pretty_delay(delay)));
+                self.block(body, "while-guard");
+                self.push("done".to_string());
+                self.trailing("end-while");
+            }
+
+            Statement::RepeatUntil { body, cond, delay } => {
+                // the body is printed once but has a point in the unrolled first
+                // iteration and one in the loop, shown joined
+                let points = get_program_points(*body.clone()).len();
+                let first = self.take(points);
+                let inv = self.take(1);
+                let guard = self.take(1);
+                let rest = self.take(points);
+                let joined = first
+                    .iter()
+                    .zip(&rest)
+                    .map(|(a, b)| (self.merge)(a, b))
+                    .collect::<Vec<_>>();
+                let tail = self.notes.drain(..).collect::<Vec<_>>();
+
+                self.notes = inv
+                    .into_iter()
+                    .chain(joined)
+                    .chain(guard)
+                    .chain(tail)
+                    .collect();
+
+                self.own_line("repeat-inv");
+                self.push("repeat".to_string());
+                self.depth += 1;
+                self.stmt(body);
+                self.own_line("repeat-guard");
+                self.depth -= 1;
+                self.push(format!("until {}{}", cond, pretty_delay(delay)));
+                self.trailing("end-repeat");
+            }
+
+            Statement::For {
+                var,
+                from,
+                to,
+                body,
+                delay,
+            } => {
+                self.own_line("for-init");
+                self.own_line("for-inv");
+                self.push(format!(
+                    "for {} in [{}, {}]{} do",
+                    var,
+                    from,
+                    to,
+                    pretty_delay(delay)
+                ));
+                self.depth += 1;
+                self.own_line("for-guard");
+                self.stmt(body);
+                self.own_line("for-step");
+                self.depth -= 1;
+                self.push("done".to_string());
+                self.trailing("end-for");
+            }
+
+            _ => {
+                self.push(stmt.to_string());
+                if let Some(note) = self.next() {
+                    self.lines.last_mut().unwrap().note = Some(note);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, body: &Spanned<Statement>, guard: &str) {
+        self.depth += 1;
+        self.own_line(guard);
+        self.stmt(body);
+        self.depth -= 1;
+    }
+
+    fn push(&mut self, text: String) {
+        self.lines.push(Line {
+            text: INDENT.repeat(self.depth) + &text,
+            note: None,
+        });
+    }
+
+    // a note about a point that is not a statement, on a line of its own
+    fn own_line(&mut self, kind: &str) {
+        if let Some(note) = self.next() {
+            self.lines.push(Line {
+                text: INDENT.repeat(self.depth) + &format!("/* {}: {} */", kind, note),
+                note: None,
+            });
+        }
+    }
+
+    fn trailing(&mut self, kind: &str) {
+        if let Some(note) = self.next() {
+            self.lines.last_mut().unwrap().note = Some(format!("{}: {}", kind, note));
+        }
+    }
+
+    fn next(&mut self) -> Option<String> {
+        self.notes.pop_front().map(|note| (self.show)(&note))
+    }
+
+    fn take(&mut self, n: usize) -> Vec<S> {
+        self.notes.drain(..n.min(self.notes.len())).collect()
+    }
+}
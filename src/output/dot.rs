@@ -0,0 +1,72 @@
+use crate::parser::ast::*;
+use crate::parser::cfg::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+
+// renders the control-flow graph of the program in the graphviz dot language,
+// labelling the node of the i-th program point with `notes[i]`
+pub fn cfg<S>(
+    name: &str,
+    ast: &Spanned<Statement>,
+    notes: &[S],
+    show: &dyn Fn(&S) -> String,
+) -> String {
+    let cfg = Cfg::new(ast);
+
+    let mut lines = vec![
+        format!("digraph {} {{", quote(name)),
+        "    node [fontname=\"monospace\"];".to_string(),
+        "    entry [shape=point];".to_string(),
+        "    exit [shape=doublecircle, label=\"\"];".to_string(),
+    ];
+
+    for (i, point) in cfg.points.iter().enumerate() {
+        let shape = match point {
+            ProgramPoint::Skip(_) | ProgramPoint::Assignment(_) => "box",
+            _ => "ellipse",
+        };
+        let label = match notes.get(i) {
+            Some(note) => format!("{}\n{}", point, show(note)),
+            None => point.to_string(),
+        };
+        lines.push(format!(
+            "    p{} [shape={}, label={}];",
+            i,
+            shape,
+            quote(&label)
+        ));
+    }
+
+    for edge in &cfg.edges {
+        let style = match edge.back {
+            true => " [style=dashed, constraint=false]",
+            _ => "",
+        };
+        lines.push(format!(
+            "    {} -> {}{};",
+            id(edge.from),
+            id(edge.to),
+            style
+        ));
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
+
+fn id(node: Node) -> String {
+    match node {
+        Node::Entry => "entry".to_string(),
+        Node::Point(i) => format!("p{}", i),
+        Node::Exit => "exit".to_string(),
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
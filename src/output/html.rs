@@ -0,0 +1,326 @@
+use crate::abstract_semantics::check::*;
+use crate::parser::ast::*;
+use crate::parser::lexer::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+use std::time::Duration;
+
+// the results of one analysis, `notes[i]` being the state at the i-th program point
+pub struct Column {
+    pub name: String,
+    pub notes: Vec<String>,
+    pub unreachable: usize,
+    pub alarms: Vec<Alarm>,
+    pub elapsed: Duration,
+}
+
+pub struct Report<'a> {
+    pub file: &'a str,
+    pub source: &'a str,
+    pub ast: &'a Spanned<Statement>,
+    pub columns: Vec<Column>,
+    // the variables at the end of the concrete run or why it has none, if it was run
+    pub final_state: Option<Result<Vec<(Identifier, String)>, String>>,
+}
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; }
+h2 { font-size: 1.1em; margin-top: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f3f3f3; }
+pre { background: #fafafa; border: 1px solid #ccc; padding: 0.5em 0; }
+.line { padding: 0 0.6em; }
+.line[title] { cursor: help; }
+.ln { color: #999; display: inline-block; width: 3em; user-select: none; }
+.kw { color: #a626a4; font-weight: bold; }
+.num { color: #986801; }
+.com { color: #a0a1a7; font-style: italic; }
+.op { color: #0184bc; }
+.hl { background: #fff3bf; }
+.mono { font-family: monospace; white-space: pre-wrap; }
+.error { color: #c0392b; font-weight: bold; }
+.warning { color: #b7791f; font-weight: bold; }
+";
+
+// links each source line to the rows of its program points, both ways, on hover
+const SCRIPT: &str = "
+function highlight(line, on) {
+  document.querySelectorAll('[data-line=\"' + line + '\"]').forEach(function (e) {
+    e.classList.toggle('hl', on);
+  });
+}
+document.querySelectorAll('[data-line]').forEach(function (e) {
+  e.addEventListener('mouseenter', function () { highlight(e.dataset.line, true); });
+  e.addEventListener('mouseleave', function () { highlight(e.dataset.line, false); });
+});
+";
+
+// renders a single self-contained html page, with no external resources
+pub fn render(report: &Report) -> String {
+    let points = get_program_points(report.ast.clone());
+
+    let mut html = vec![
+        "<!DOCTYPE html>".to_string(),
+        "<html>".to_string(),
+        "<head>".to_string(),
+        "<meta charset=\"utf-8\">".to_string(),
+        format!("<title>whilep report: {}</title>", escape(report.file)),
+        format!("<style>{}</style>", STYLE),
+        "</head>".to_string(),
+        "<body>".to_string(),
+        format!("<h1>whilep report: {}</h1>", escape(report.file)),
+    ];
+
+    html.push("<h2>Source</h2>".to_string());
+    html.push(source(report, &points));
+
+    html.push("<h2>Program points</h2>".to_string());
+    html.push(invariants(report, &points));
+
+    if let Some(final_state) = &report.final_state {
+        html.push("<h2>Concrete run</h2>".to_string());
+        html.push(concrete(final_state));
+    }
+
+    html.push("<h2>Alarms</h2>".to_string());
+    html.push(alarms(report));
+
+    html.push("<h2>Statistics</h2>".to_string());
+    html.push(statistics(report, &points));
+
+    html.push(format!("<script>{}</script>", SCRIPT));
+    html.push("</body>".to_string());
+    html.push("</html>".to_string());
+    html.join("\n") + "\n"
+}
+
+// --- sections
+
+// the highlighted source, each line showing the states of its program points on hover
+fn source(report: &Report, points: &[ProgramPoint]) -> String {
+    let lines = highlight(report.source);
+
+    let body = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let notes = points
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.span().line == i + 1)
+                .flat_map(|(j, p)| {
+                    report
+                        .columns
+                        .iter()
+                        .map(move |c| format!("{} ({}): {}", p, c.name, c.notes[j]))
+                })
+                .collect::<Vec<_>>();
+            let title = match notes.is_empty() {
+                true => String::new(),
+                _ => format!(" title=\"{}\"", escape(&notes.join("\n"))),
+            };
+            format!(
+                "<div class=\"line\" data-line=\"{}\"{}><span class=\"ln\">{}</span>{}</div>",
+                i + 1,
+                title,
+                i + 1,
+                line
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("");
+
+    format!("<pre>{}</pre>", body)
+}
+
+fn invariants(report: &Report, points: &[ProgramPoint]) -> String {
+    let mut headers = vec![
+        "#".to_string(),
+        "Location".to_string(),
+        "Program point".to_string(),
+    ];
+    headers.extend(report.columns.iter().map(|c| escape(&c.name)));
+
+    let rows = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let mut row = vec![
+                i.to_string(),
+                escape(&location(report.file, &p.span())),
+                format!("<span class=\"mono\">{}</span>", escape(&p.to_string())),
+            ];
+            row.extend(
+                report
+                    .columns
+                    .iter()
+                    .map(|c| format!("<span class=\"mono\">{}</span>", escape(&c.notes[i]))),
+            );
+            (Some(p.span().line), row)
+        })
+        .collect();
+
+    table(&headers, rows)
+}
+
+fn concrete(final_state: &Result<Vec<(Identifier, String)>, String>) -> String {
+    match final_state {
+        Ok(vars) => {
+            let headers = vec!["Var".to_string(), "Val".to_string()];
+            let rows = vars
+                .iter()
+                .map(|(var, val)| (None, vec![escape(var), escape(val)]))
+                .collect();
+            table(&headers, rows)
+        }
+        Err(e) => format!("<p class=\"error mono\">{}</p>", escape(e)),
+    }
+}
+
+fn alarms(report: &Report) -> String {
+    let alarms = report
+        .columns
+        .iter()
+        .flat_map(|c| c.alarms.iter().map(move |a| (&c.name, a)))
+        .collect::<Vec<_>>();
+
+    if alarms.is_empty() {
+        return "<p>No alarms.</p>".to_string();
+    }
+
+    let headers = ["Severity", "Rule", "Location", "Message", "Domain"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect::<Vec<_>>();
+    let rows = alarms
+        .iter()
+        .map(|(name, a)| {
+            let row = vec![
+                format!("<span class=\"{0}\">{0}</span>", a.severity),
+                a.rule.to_string(),
+                escape(&location(report.file, &a.span)),
+                escape(&a.message),
+                escape(name),
+            ];
+            (Some(a.span.line), row)
+        })
+        .collect();
+
+    table(&headers, rows)
+}
+
+fn statistics(report: &Report, points: &[ProgramPoint]) -> String {
+    let loops = points
+        .iter()
+        .filter(|p| {
+            matches!(
+                p,
+                ProgramPoint::WhileInv { .. }
+                    | ProgramPoint::RepeatInv { .. }
+                    | ProgramPoint::ForInv { .. }
+            )
+        })
+        .count();
+
+    let summary = format!(
+        "<p>{} line(s), {} program point(s), {} loop(s).</p>",
+        report.source.lines().count(),
+        points.len(),
+        loops
+    );
+
+    let headers = ["Analysis", "Unreachable points", "Alarms", "Time"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect::<Vec<_>>();
+    let rows = report
+        .columns
+        .iter()
+        .map(|c| {
+            let row = vec![
+                escape(&c.name),
+                c.unreachable.to_string(),
+                c.alarms.len().to_string(),
+                format!("{:.3} ms", c.elapsed.as_secs_f64() * 1000.0),
+            ];
+            (None, row)
+        })
+        .collect();
+
+    summary + &table(&headers, rows)
+}
+
+// --- helpers
+
+// cells are already escaped, a row with a line is linked to that source line
+fn table(headers: &[String], rows: Vec<(Option<usize>, Vec<String>)>) -> String {
+    let head = headers
+        .iter()
+        .map(|h| format!("<th>{}</th>", h))
+        .collect::<String>();
+    let body = rows
+        .iter()
+        .map(|(line, cells)| {
+            let line = line.map_or(String::new(), |l| format!(" data-line=\"{}\"", l));
+            let cells = cells
+                .iter()
+                .map(|c| format!("<td>{}</td>", c))
+                .collect::<String>();
+            format!("<tr{}>{}</tr>", line, cells)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("<table>\n<tr>{}</tr>\n{}\n</table>", head, body)
+}
+
+// the source split in lines of html, with a class on each token
+fn highlight(source: &str) -> Vec<String> {
+    let mut segments = vec![];
+    let mut offset = 0;
+
+    for (start, token, end) in Lexer::new(source).flatten() {
+        segments.push(("", &source[offset..start]));
+        let class = match token {
+            Token::Identifier(_) => "",
+            Token::Number(_) => "num",
+            Token::Comment(_) => "com",
+            t if t.is_keyword() => "kw",
+            _ => "op",
+        };
+        segments.push((class, &source[start..end]));
+        offset = end;
+    }
+    segments.push(("", &source[offset..]));
+
+    let mut lines = vec![String::new()];
+    for (class, text) in segments {
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            let part = escape(part);
+            let last = lines.last_mut().unwrap();
+            match (class, part.is_empty()) {
+                (_, true) => (),
+                ("", _) => last.push_str(&part),
+                _ => last.push_str(&format!("<span class=\"{}\">{}</span>", class, part)),
+            }
+        }
+    }
+
+    if source.ends_with('\n') {
+        lines.pop();
+    }
+    lines
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
@@ -0,0 +1,126 @@
+use crate::abstract_semantics;
+use crate::abstract_semantics::check::*;
+use crate::analysis::*;
+use crate::concrete_semantics;
+use crate::domain::domain::*;
+use crate::parser::ast::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+use serde_json::{json, Map, Value};
+
+// bumped whenever a field changes meaning or disappears
+pub const SCHEMA_VERSION: u64 = 2;
+
+pub fn program(ast: &Spanned<Statement>) -> Value {
+    json!({
+        "source": ast.to_string(),
+        "ast": ast,
+    })
+}
+
+pub fn execution(
+    file: &str,
+    result: &Result<Option<Execution>, Spanned<ArithmeticExprError>>,
+) -> Value {
+    match result {
+        Ok(Some(execution)) => json!({
+            "points": execution
+                .points
+                .iter()
+                .enumerate()
+                .map(|(i, p)| {
+                    let mut point = point(file, i, &p.point);
+                    point["states"] = p.states.iter().map(concrete_state).collect();
+                    point
+                })
+                .collect::<Vec<_>>(),
+            "final_state": concrete_state(&execution.final_state),
+        }),
+        Ok(None) => json!({ "final_state": null }),
+        Err(e) => json!({ "error": diagnostic(file, &e.span, &e.to_string()) }),
+    }
+}
+
+pub fn analysis<T: Domain>(file: &str, analysis: &Analysis<T>) -> Value {
+    json!({
+        "points": analysis
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let mut point = point(file, i, &p.point);
+                point["invariant"] = abstract_state(&p.invariant);
+                point
+            })
+            .collect::<Vec<_>>(),
+        "exit_state": abstract_state(&analysis.exit_state),
+        "alarms": analysis.alarms.iter().map(|a| alarm(file, a)).collect::<Vec<_>>(),
+    })
+}
+
+pub fn syntax_errors(file: &str, errors: &[Spanned<SyntaxError>]) -> Value {
+    errors
+        .iter()
+        .map(|e| diagnostic(file, &e.span, &e.to_string()))
+        .collect()
+}
+
+// --- helpers
+
+fn point(file: &str, index: usize, point: &ProgramPoint) -> Value {
+    json!({
+        "index": index,
+        "kind": point.kind(),
+        "label": point.to_string(),
+        "location": location(file, &point.span()),
+    })
+}
+
+pub fn diagnostic(file: &str, span: &Span, message: &str) -> Value {
+    json!({
+        "message": message,
+        "location": location(file, span),
+    })
+}
+
+fn alarm(file: &str, alarm: &Alarm) -> Value {
+    let mut value = diagnostic(file, &alarm.span, &alarm.message);
+    value["rule"] = json!(alarm.rule);
+    value["severity"] = json!(alarm.severity.to_string());
+    value
+}
+
+fn location(file: &str, span: &Span) -> Value {
+    json!({
+        "file": file,
+        "line": span.line,
+        "column": span.column,
+        "start": span.start,
+        "end": span.end,
+    })
+}
+
+pub fn concrete_state(state: &concrete_semantics::state::State) -> Value {
+    Value::Object(
+        state
+            .iter()
+            .map(|(var, val)| (var.clone(), json!(val)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+// a bottom state is `{"bottom": true}`, anything else lists its variables with
+// their values as the domain serializes them, so that an unreachable point never
+// looks like one reached with no variables
+fn abstract_state<T: Domain>(state: &abstract_semantics::state::State<T>) -> Value {
+    match state {
+        abstract_semantics::state::State::Bottom => json!({ "bottom": true }),
+        abstract_semantics::state::State::Just(vars) => json!({
+            "bottom": false,
+            "vars": vars
+                .iter()
+                .map(|(var, val)| (var.clone(), json!(val)))
+                .collect::<Map<_, _>>(),
+        }),
+    }
+}
@@ -0,0 +1,5 @@
+pub mod annotate;
+pub mod dot;
+pub mod html;
+pub mod json;
+pub mod sarif;
@@ -0,0 +1,119 @@
+use crate::abstract_semantics::check::*;
+use crate::parser::span::*;
+use serde_json::{json, Value};
+
+pub const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+// every rule an alarm can break, with its description and its default severity
+pub const RULES: &[(&str, &str, Severity)] = &[
+    (
+        "syntax-error",
+        "The program cannot be parsed",
+        Severity::Error,
+    ),
+    (
+        "division-by-zero",
+        "The divisor of a division may be zero",
+        Severity::Warning,
+    ),
+    (
+        "invalid-interval-bounds",
+        "The lower bound of an interval may be greater than its upper bound",
+        Severity::Warning,
+    ),
+    (
+        "undefined-variable",
+        "A variable is read before being assigned",
+        Severity::Error,
+    ),
+    (
+        "non-terminating-loop",
+        "A reachable loop has no terminating execution",
+        Severity::Warning,
+    ),
+];
+
+// a sarif log with a single run of whilep over `file`
+pub fn log(file: &str, source: &str, alarms: &[Alarm]) -> Value {
+    log_files(&[(file, source, alarms)])
+}
+
+// a sarif log with a single run of whilep over several files, given with their
+// source and their alarms
+pub fn log_files(files: &[(&str, &str, &[Alarm])]) -> Value {
+    let rules = RULES
+        .iter()
+        .map(|(id, description, severity)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+                "defaultConfiguration": { "level": level(*severity) },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = files
+        .iter()
+        .flat_map(|(file, source, alarms)| {
+            let index = LineIndex::new(source);
+            alarms.iter().map(move |alarm| {
+                // sarif counts characters where spans count bytes
+                let (start, end) = (
+                    source[..alarm.span.start].chars().count(),
+                    source[..alarm.span.end].chars().count(),
+                );
+                (file, index.position(alarm.span.end), (start, end), alarm)
+            })
+        })
+        .map(|(file, (end_line, end_column), (start, end), alarm)| {
+            json!({
+                "ruleId": alarm.rule,
+                "ruleIndex": RULES.iter().position(|(id, _, _)| *id == alarm.rule),
+                "level": level(alarm.severity),
+                "message": { "text": alarm.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": file },
+                        "region": {
+                            "startLine": alarm.span.line,
+                            "startColumn": alarm.span.column,
+                            "endLine": end_line,
+                            "endColumn": end_column,
+                            "charOffset": start,
+                            "charLength": end - start,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            // columns and offsets count characters, as in the other outputs
+            "columnKind": "unicodeCodePoints",
+            "tool": {
+                "driver": {
+                    "name": "whilep",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "artifacts": files
+                .iter()
+                .map(|(file, _, _)| json!({ "location": { "uri": file } }))
+                .collect::<Vec<_>>(),
+            "results": results,
+        }],
+    })
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
@@ -1,102 +1,274 @@
+use crate::parser::lexer::*;
+use crate::parser::span::*;
+use crate::parser::visit::*;
 use crate::{max, types::integer::*};
-use lalrpop_util::{lalrpop_mod, lexer::Token, ParseError};
+use lalrpop_util::{lalrpop_mod, ParseError};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::fmt;
 
-lalrpop_mod!(pub whilep);
+lalrpop_mod!(
+    #[allow(clippy::all, unused_imports)]
+    whilep
+);
 
 pub type Identifier = String;
 
-pub fn parse(source: &str) -> Result<Statement, ParseError<usize, Token, &'static str>> {
-    whilep::StmtParser::new().parse(source)
+thread_local! {
+    // when set, `&&` and `||` evaluate both operands instead of short-circuiting
+    pub static STRICT_EVALUATION: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+    // when set, the delay of the loops with no `@delay` of their own
+    pub static WIDENING_DELAY: std::cell::Cell<Option<i64>> = const { std::cell::Cell::new(None) };
 }
 
+pub fn parse(source: &str) -> Result<Spanned<Statement>, Vec<Spanned<SyntaxError>>> {
+    match parse_partial(source) {
+        (Some(ast), errors) if errors.is_empty() => Ok(ast),
+        (_, errors) => Err(errors),
+    }
+}
+
+pub fn parse_partial(source: &str) -> (Option<Spanned<Statement>>, Vec<Spanned<SyntaxError>>) {
+    let index = LineIndex::new(source);
+    let mut lexical_errors = vec![];
+    let mut recovered = vec![];
+
+    let tokens = Lexer::new(source).filter_map(|t| match t {
+        Ok((_, Token::Comment(_), _)) => None,
+        Ok(token) => Some(Ok(token)),
+        Err(e) => {
+            let (start, end) = e.location();
+            lexical_errors.push(e);
+            Some(Ok((start, Token::Invalid, end)))
+        }
+    });
+
+    let result = whilep::StmtParser::new().parse(&index, &mut recovered, tokens);
+
+    let mut errors = recovered
+        .into_iter()
+        .map(|r| r.error)
+        .chain(result.as_ref().err().cloned())
+        .map(|e| syntax_error(source, &index, &lexical_errors, e))
+        .collect::<Vec<_>>();
+
+    // recovery may skip an invalid token without the parser ever failing on
+    // it, so every lexical error is reported unless it already is
+    let reported: HashSet<_> = errors.iter().map(|e| (e.span.start, e.span.end)).collect();
+    for e in lexical_errors {
+        let (start, end) = e.location();
+        if !reported.contains(&(start, end)) {
+            let span = index.span(start, end);
+            errors.push(Spanned::new(SyntaxError::Lexical(e), span));
+        }
+    }
+    errors.sort_by_key(|e| e.span.start);
+
+    (result.ok(), errors)
+}
+
+fn syntax_error(
+    source: &str,
+    index: &LineIndex,
+    lexical_errors: &[LexicalError],
+    error: ParseError<usize, Token, LexicalError>,
+) -> Spanned<SyntaxError> {
+    let token_span = |start: usize, end: usize| index.span(start, end);
+
+    match error {
+        ParseError::InvalidToken { location } => Spanned::new(
+            SyntaxError::InvalidToken,
+            token_span(location, location + 1),
+        ),
+        ParseError::UnrecognizedEof { location, expected } => Spanned::new(
+            SyntaxError::UnexpectedEof { expected },
+            token_span(location, location),
+        ),
+        ParseError::UnrecognizedToken {
+            token: (start, Token::Invalid, end),
+            ..
+        } => match lexical_errors.iter().find(|e| e.location().0 == start) {
+            Some(e) => Spanned::new(SyntaxError::Lexical(e.clone()), token_span(start, end)),
+            None => Spanned::new(SyntaxError::InvalidToken, token_span(start, end)),
+        },
+        ParseError::UnrecognizedToken {
+            token: (start, token, end),
+            expected,
+        } if token.is_keyword() && in_identifier_position(source, end, &expected) => Spanned::new(
+            SyntaxError::ReservedKeyword(token.to_string()),
+            token_span(start, end),
+        ),
+        ParseError::UnrecognizedToken {
+            token: (start, token, end),
+            expected,
+        } => Spanned::new(
+            SyntaxError::UnexpectedToken {
+                token: token.to_string(),
+                expected,
+            },
+            token_span(start, end),
+        ),
+        ParseError::ExtraToken {
+            token: (start, token, end),
+        } => Spanned::new(
+            SyntaxError::UnexpectedToken {
+                token: token.to_string(),
+                expected: vec![],
+            },
+            token_span(start, end),
+        ),
+        ParseError::User { error } => {
+            let (start, end) = error.location();
+            Spanned::new(SyntaxError::Lexical(error), token_span(start, end))
+        }
+    }
+}
+
+// whether a keyword the parser did not expect stands for a variable: it is the only
+// thing that fits, as after `for`, or it is assigned to
+fn in_identifier_position(source: &str, end: usize, expected: &[String]) -> bool {
+    let identifier = "\"identifier\"".to_string();
+    let next = Lexer::new(&source[end..]).find(|t| !matches!(t, Ok((_, Token::Comment(_), _))));
+
+    match (expected, next) {
+        ([only], _) => *only == identifier,
+        (_, Some(Ok((_, token, _)))) => {
+            expected.contains(&identifier)
+                && matches!(
+                    token,
+                    Token::Assign
+                        | Token::AddAssign
+                        | Token::SubAssign
+                        | Token::MulAssign
+                        | Token::DivAssign
+                        | Token::Increment
+                        | Token::Decrement
+                )
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxError {
+    InvalidToken,
+    UnexpectedEof {
+        expected: Vec<String>,
+    },
+    UnexpectedToken {
+        token: String,
+        expected: Vec<String>,
+    },
+    ReservedKeyword(String),
+    Lexical(LexicalError),
+}
+
+#[derive(Debug, Clone)]
 pub enum ArithmeticExprError {
     DivByZero,
     InvalidIntervalBounds,
     VariableNotFound,
+    // the result is out of the range of a signed 64-bit integer
+    Overflow,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Statement {
     Skip,
-    Chain(Box<Statement>, Box<Statement>),
+    Error(String),
+    Chain(Box<Spanned<Statement>>, Box<Spanned<Statement>>),
     Assignment {
         var: Identifier,
-        val: Box<ArithmeticExpr>,
+        val: Box<Spanned<ArithmeticExpr>>,
+    },
+    CompoundAssignment {
+        var: Identifier,
+        op: ArithmeticOp,
+        val: Box<Spanned<ArithmeticExpr>>,
     },
+    Increment(Identifier),
+    Decrement(Identifier),
     If {
-        cond: Box<BooleanExpr>,
-        s1: Box<Statement>,
-        s2: Box<Statement>,
+        cond: Box<Spanned<BooleanExpr>>,
+        s1: Box<Spanned<Statement>>,
+        s2: Box<Spanned<Statement>>,
     },
     While {
-        cond: Box<BooleanExpr>,
-        body: Box<Statement>,
+        cond: Box<Spanned<BooleanExpr>>,
+        body: Box<Spanned<Statement>>,
         delay: Option<i64>,
     },
 
     RepeatUntil {
-        body: Box<Statement>,
-        cond: Box<BooleanExpr>,
+        body: Box<Spanned<Statement>>,
+        cond: Box<Spanned<BooleanExpr>>,
+        delay: Option<i64>,
+    },
+
+    For {
+        var: Identifier,
+        from: Box<Spanned<ArithmeticExpr>>,
+        to: Box<Spanned<ArithmeticExpr>>,
+        body: Box<Spanned<Statement>>,
         delay: Option<i64>,
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum ArithmeticExpr {
     Number(Integer),
-    Interval(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
+    Interval(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
     Variable(Identifier),
-    Add(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
-    Sub(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
-    Mul(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
-    Div(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
+    Add(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
+    Sub(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
+    Mul(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
+    Div(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
     PostIncrement(Identifier),
     PostDecrement(Identifier),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum BooleanExpr {
     True,
     False,
-    Not(Box<BooleanExpr>),
-    And(Box<BooleanExpr>, Box<BooleanExpr>),
-    Or(Box<BooleanExpr>, Box<BooleanExpr>),
-    NumEq(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
-    NumNotEq(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
-    NumLt(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
-    NumGt(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
-    NumLtEq(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
-    NumGtEq(Box<ArithmeticExpr>, Box<ArithmeticExpr>),
+    Not(Box<Spanned<BooleanExpr>>),
+    And(Box<Spanned<BooleanExpr>>, Box<Spanned<BooleanExpr>>),
+    Or(Box<Spanned<BooleanExpr>>, Box<Spanned<BooleanExpr>>),
+    NumEq(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
+    NumNotEq(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
+    NumLt(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
+    NumGt(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
+    NumLtEq(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
+    NumGtEq(Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>),
 }
 
 impl Statement {
     pub fn get_max_number(&self) -> Option<i64> {
-        match self.clone() {
-            Statement::Skip => None,
-            Statement::Chain(s1, s2) => max!(s1.get_max_number(), s2.get_max_number()),
-            Statement::Assignment { var: _, val } => val.get_max_number(),
-            Statement::If { cond, s1, s2 } => {
-                max!(
-                    cond.get_max_number(),
-                    s1.get_max_number(),
-                    s2.get_max_number()
-                )
-            }
-            Statement::While {
-                cond,
-                body,
-                delay: _,
-            } => {
-                max!(cond.get_max_number(), body.get_max_number())
-            }
-            Statement::RepeatUntil {
-                body,
-                cond,
-                delay: _,
-            } => {
-                max!(cond.get_max_number(), body.get_max_number())
-            }
+        let mut max = MaxNumber(None);
+        max.stmt(self);
+        max.0
+    }
+}
+
+impl ArithmeticOp {
+    pub fn apply(
+        &self,
+        a1: Box<Spanned<ArithmeticExpr>>,
+        a2: Box<Spanned<ArithmeticExpr>>,
+    ) -> ArithmeticExpr {
+        match self {
+            ArithmeticOp::Add => ArithmeticExpr::Add(a1, a2),
+            ArithmeticOp::Sub => ArithmeticExpr::Sub(a1, a2),
+            ArithmeticOp::Mul => ArithmeticExpr::Mul(a1, a2),
+            ArithmeticOp::Div => ArithmeticExpr::Div(a1, a2),
         }
     }
 }
@@ -121,36 +293,58 @@ impl ArithmeticExpr {
         }
     }
 
-    pub fn get_max_number(&self) -> Option<i64> {
-        match self.clone() {
-            ArithmeticExpr::Number(Integer::Value(n)) => Some(n.abs() + 1),
-
+    // the variables the expression increments or decrements
+    pub fn written_variables(&self) -> Vec<Identifier> {
+        match self {
+            ArithmeticExpr::Number(_) | ArithmeticExpr::Variable(_) => vec![],
+            ArithmeticExpr::PostIncrement(var) | ArithmeticExpr::PostDecrement(var) => {
+                vec![var.clone()]
+            }
             ArithmeticExpr::Interval(a1, a2)
             | ArithmeticExpr::Add(a1, a2)
             | ArithmeticExpr::Sub(a1, a2)
             | ArithmeticExpr::Mul(a1, a2)
             | ArithmeticExpr::Div(a1, a2) => {
-                max!(a1.get_max_number(), a2.get_max_number())
+                [a1.written_variables(), a2.written_variables()].concat()
             }
+        }
+    }
 
+    // the operand of `-a`, which parses as `0 - a`
+    fn negated(&self) -> Option<&Spanned<ArithmeticExpr>> {
+        match self {
+            ArithmeticExpr::Sub(a1, a2) if matches!(a1.node, ArithmeticExpr::Number(ZERO)) => {
+                Some(a2)
+            }
             _ => None,
         }
     }
 }
 
+impl Precedence for ArithmeticExpr {
+    fn precedence(&self) -> u8 {
+        match self {
+            ArithmeticExpr::Sub(_, _) if self.negated().is_some() => 3,
+            ArithmeticExpr::Add(_, _) | ArithmeticExpr::Sub(_, _) => 1,
+            ArithmeticExpr::Mul(_, _) | ArithmeticExpr::Div(_, _) => 2,
+            _ => 3,
+        }
+    }
+}
+
 impl BooleanExpr {
     pub fn negate(&self) -> BooleanExpr {
         match self.clone() {
             BooleanExpr::True => BooleanExpr::False,
             BooleanExpr::False => BooleanExpr::True,
-            BooleanExpr::Not(b) => *b,
+            BooleanExpr::Not(b) => b.node,
             BooleanExpr::And(b1, b2) => BooleanExpr::Or(
-                Box::new(BooleanExpr::Not(b1)),
-                Box::new(BooleanExpr::Not(b2)),
+                Spanned::boxed(BooleanExpr::Not(b1.clone()), b1.span),
+                Spanned::boxed(BooleanExpr::Not(b2.clone()), b2.span),
             ),
             BooleanExpr::Or(b1, b2) => BooleanExpr::And(
-                Box::new(BooleanExpr::Not(b1)),
-                Box::new(BooleanExpr::Not(b2)),
+                Spanned::boxed(BooleanExpr::Not(b1.clone()), b1.span),
+                Spanned::boxed(BooleanExpr::Not(b2.clone()), b2.span),
             ),
             BooleanExpr::NumEq(a1, a2) => BooleanExpr::NumNotEq(a1, a2),
             BooleanExpr::NumNotEq(a1, a2) => BooleanExpr::NumEq(a1, a2),
@@ -160,16 +354,15 @@ impl BooleanExpr {
             BooleanExpr::NumGtEq(a1, a2) => BooleanExpr::NumLt(a1, a2),
         }
     }
+}
 
-    pub fn get_max_number(&self) -> Option<i64> {
-        match self.clone() {
-            BooleanExpr::NumEq(a1, a2)
-            | BooleanExpr::NumNotEq(a1, a2)
-            | BooleanExpr::NumLt(a1, a2)
-            | BooleanExpr::NumGt(a1, a2)
-            | BooleanExpr::NumLtEq(a1, a2)
-            | BooleanExpr::NumGtEq(a1, a2) => max!(a1.get_max_number(), a2.get_max_number()),
-            _ => None,
+impl Precedence for BooleanExpr {
+    fn precedence(&self) -> u8 {
+        match self {
+            BooleanExpr::Or(_, _) => 1,
+            BooleanExpr::And(_, _) => 2,
+            BooleanExpr::True | BooleanExpr::False | BooleanExpr::Not(_) => 4,
+            _ => 3,
         }
     }
 }
@@ -178,13 +371,37 @@ impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Statement::Skip => write!(f, "skip"),
+            Statement::Error(source) => write!(f, "{}", source),
             Statement::Assignment { var, val } => write!(f, "{} := {}", var, val),
+            Statement::CompoundAssignment { var, op, val } => {
+                write!(f, "{} {}= {}", var, op, val)
+            }
+            Statement::Increment(var) => write!(f, "{}++", var),
+            Statement::Decrement(var) => write!(f, "{}--", var),
             Statement::Chain(s1, s2) => write!(f, "{}; {}", s1, s2),
             Statement::If { cond, s1, s2 } => write!(f, "if {} then {} else {} end", cond, s1, s2),
             Statement::While { cond, body, .. } => write!(f, "while {} do {} done", cond, body),
             Statement::RepeatUntil { cond, body, .. } => {
                 write!(f, "repeat {} until {}", body, cond)
             }
+            Statement::For {
+                var,
+                from,
+                to,
+                body,
+                ..
+            } => write!(f, "for {} in [{}, {}] do {} done", var, from, to, body),
+        }
+    }
+}
+
+impl fmt::Display for ArithmeticOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArithmeticOp::Add => write!(f, "+"),
+            ArithmeticOp::Sub => write!(f, "-"),
+            ArithmeticOp::Mul => write!(f, "*"),
+            ArithmeticOp::Div => write!(f, "/"),
         }
     }
 }
@@ -195,10 +412,18 @@ impl fmt::Display for ArithmeticExpr {
             ArithmeticExpr::Number(n) => write!(f, "{}", n),
             ArithmeticExpr::Interval(a, b) => write!(f, "[{}, {}]", a, b),
             ArithmeticExpr::Variable(s) => write!(f, "{}", s),
-            ArithmeticExpr::Add(a, b) => write!(f, "({} + {})", a, b),
-            ArithmeticExpr::Sub(a, b) => write!(f, "({} - {})", a, b),
-            ArithmeticExpr::Mul(a, b) => write!(f, "({} * {})", a, b),
-            ArithmeticExpr::Div(a, b) => write!(f, "({} / {})", a, b),
+            ArithmeticExpr::Add(a, b) => write!(f, "{} + {}", wrap(&a.node, 1), wrap(&b.node, 2)),
+            ArithmeticExpr::Sub(a, b) => match self.negated() {
+                // `-n` would be a literal and `--` a decrement
+                Some(operand) => match (&operand.node, wrap(&operand.node, 3)) {
+                    (ArithmeticExpr::Number(_), operand) => write!(f, "-({})", operand),
+                    (_, operand) if operand.starts_with('-') => write!(f, "-({})", operand),
+                    (_, operand) => write!(f, "-{}", operand),
+                },
+                None => write!(f, "{} - {}", wrap(&a.node, 1), wrap(&b.node, 2)),
+            },
+            ArithmeticExpr::Mul(a, b) => write!(f, "{} * {}", wrap(&a.node, 2), wrap(&b.node, 3)),
+            ArithmeticExpr::Div(a, b) => write!(f, "{} / {}", wrap(&a.node, 2), wrap(&b.node, 3)),
             ArithmeticExpr::PostIncrement(s) => write!(f, "{}++", s),
             ArithmeticExpr::PostDecrement(s) => write!(f, "{}--", s),
         }
@@ -210,15 +435,15 @@ impl fmt::Display for BooleanExpr {
         match self {
             BooleanExpr::True => write!(f, "true"),
             BooleanExpr::False => write!(f, "false"),
-            BooleanExpr::Not(b) => write!(f, "!{}", b),
-            BooleanExpr::And(b1, b2) => write!(f, "({} && {})", b1, b2),
-            BooleanExpr::Or(b1, b2) => write!(f, "({} || {})", b1, b2),
-            BooleanExpr::NumEq(a1, a2) => write!(f, "({} == {})", a1, a2),
-            BooleanExpr::NumNotEq(a1, a2) => write!(f, "({} != {})", a1, a2),
-            BooleanExpr::NumLt(a1, a2) => write!(f, "({} < {})", a1, a2),
-            BooleanExpr::NumGt(a1, a2) => write!(f, "({} > {})", a1, a2),
-            BooleanExpr::NumLtEq(a1, a2) => write!(f, "({} <= {})", a1, a2),
-            BooleanExpr::NumGtEq(a1, a2) => write!(f, "({} >= {})", a1, a2),
+            BooleanExpr::Not(b) => write!(f, "!{}", wrap(&b.node, 4)),
+            BooleanExpr::And(b1, b2) => write!(f, "{} && {}", wrap(&b1.node, 2), wrap(&b2.node, 3)),
+            BooleanExpr::Or(b1, b2) => write!(f, "{} || {}", wrap(&b1.node, 1), wrap(&b2.node, 2)),
+            BooleanExpr::NumEq(a1, a2) => write!(f, "{} == {}", a1, a2),
+            BooleanExpr::NumNotEq(a1, a2) => write!(f, "{} != {}", a1, a2),
+            BooleanExpr::NumLt(a1, a2) => write!(f, "{} < {}", a1, a2),
+            BooleanExpr::NumGt(a1, a2) => write!(f, "{} > {}", a1, a2),
+            BooleanExpr::NumLtEq(a1, a2) => write!(f, "{} <= {}", a1, a2),
+            BooleanExpr::NumGtEq(a1, a2) => write!(f, "{} >= {}", a1, a2),
         }
     }
 }
@@ -229,17 +454,109 @@ impl fmt::Display for ArithmeticExprError {
             ArithmeticExprError::DivByZero => write!(f, "division by zero"),
             ArithmeticExprError::InvalidIntervalBounds => write!(f, "invalid interval bounds"),
             ArithmeticExprError::VariableNotFound => write!(f, "variable not found"),
+            ArithmeticExprError::Overflow => write!(f, "integer overflow"),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxError::InvalidToken => write!(f, "invalid token"),
+            SyntaxError::UnexpectedEof { expected } => {
+                write!(f, "unexpected end of file{}", format_expected(expected))
+            }
+            SyntaxError::UnexpectedToken { token, expected } => {
+                write!(
+                    f,
+                    "unexpected token `{}`{}",
+                    token,
+                    format_expected(expected)
+                )
+            }
+            SyntaxError::ReservedKeyword(keyword) => {
+                write!(f, "`{}` is a reserved keyword", keyword)
+            }
+            SyntaxError::Lexical(error) => write!(f, "{}", error),
         }
     }
 }
 
 // --- helpers
 
+// largest literal magnitude plus one, used as the default loop delay and `--bounds auto`
+struct MaxNumber(Option<i64>);
+
+impl MaxNumber {
+    fn literal(&mut self, n: Integer) {
+        if let Integer::Value(n) = n {
+            self.0 = max!(self.0, Some(n.saturating_abs().saturating_add(1)));
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Statement) {
+        // the implicit step of one
+        if let Statement::Increment(_) | Statement::Decrement(_) | Statement::For { .. } = stmt {
+            self.literal(ONE);
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn aexpr(&mut self, expr: &ArithmeticExpr) {
+        if let ArithmeticExpr::Number(n) = expr {
+            self.literal(*n);
+        }
+        walk_aexpr(self, expr);
+    }
+}
+
+impl Visitor for MaxNumber {
+    fn visit_stmt(&mut self, stmt: &Spanned<Statement>) {
+        self.stmt(stmt)
+    }
+
+    fn visit_aexpr(&mut self, expr: &Spanned<ArithmeticExpr>) {
+        self.aexpr(expr)
+    }
+}
+
+// binding strength of an operator, higher binds tighter
+pub trait Precedence {
+    fn precedence(&self) -> u8;
+}
+
+fn wrap<T: Precedence + fmt::Display>(expr: &T, min: u8) -> String {
+    match expr.precedence() < min {
+        true => format!("({})", expr),
+        _ => expr.to_string(),
+    }
+}
+
+pub fn negation(expr: Spanned<ArithmeticExpr>, minus: Span, span: Span) -> Spanned<ArithmeticExpr> {
+    Spanned::new(
+        ArithmeticExpr::Sub(
+            Spanned::boxed(ArithmeticExpr::Number(ZERO), minus),
+            Box::new(expr),
+        ),
+        span,
+    )
+}
+
+fn format_expected(expected: &[String]) -> String {
+    match expected.len() {
+        0 => String::new(),
+        1 => format!(", expected {}", expected[0]),
+        _ => format!(", expected one of {}", expected.join(", ")),
+    }
+}
+
 pub fn get_loop_delay(stmt: &Statement) -> i64 {
     match stmt {
-        Statement::While { delay, .. } | Statement::RepeatUntil { delay, .. } => {
-            delay.unwrap_or(stmt.get_max_number().unwrap_or(0))
-        }
+        Statement::While { delay, .. }
+        | Statement::RepeatUntil { delay, .. }
+        | Statement::For { delay, .. } => delay
+            .or(WIDENING_DELAY.get())
+            .unwrap_or(stmt.get_max_number().unwrap_or(0)),
         _ => panic!("[ERROR] not a loop statement"),
     }
 }
@@ -0,0 +1,125 @@
+use crate::parser::ast::*;
+use crate::parser::program_point::*;
+use crate::parser::span::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Node {
+    Entry,
+    Point(usize),
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: Node,
+    pub to: Node,
+    // the edge closing a loop, from the end of its body to its head
+    pub back: bool,
+}
+
+// the control-flow graph of a program, whose nodes are the program points in the
+// order of `get_program_points`; a repeat body appears twice, as the semantics
+// unroll its first iteration
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    pub points: Vec<ProgramPoint>,
+    pub edges: Vec<Edge>,
+}
+
+impl Cfg {
+    pub fn new(ast: &Spanned<Statement>) -> Self {
+        let mut builder = Builder {
+            next: 0,
+            edges: vec![],
+        };
+        let exits = builder.stmt(ast, vec![Node::Entry]);
+        builder.connect(&exits, Node::Exit, false);
+
+        Cfg {
+            points: get_program_points(ast.clone()),
+            edges: builder.edges,
+        }
+    }
+
+    pub fn predecessors(&self, node: Node) -> Vec<Node> {
+        self.edges
+            .iter()
+            .filter(|e| e.to == node)
+            .map(|e| e.from)
+            .collect()
+    }
+}
+
+struct Builder {
+    next: usize,
+    edges: Vec<Edge>,
+}
+
+impl Builder {
+    // adds the nodes of `stmt` reached from `preds` and returns the nodes leaving it
+    fn stmt(&mut self, stmt: &Spanned<Statement>, preds: Vec<Node>) -> Vec<Node> {
+        match &stmt.node {
+            Statement::Error(_) => preds,
+
+            Statement::Chain(s1, s2) => {
+                let exits = self.stmt(s1, preds);
+                self.stmt(s2, exits)
+            }
+
+            Statement::If { s1, s2, .. } => {
+                let guard = self.node(&preds);
+                let mut exits = self.stmt(s1, vec![guard]);
+                let else_guard = self.node(&preds);
+                exits.extend(self.stmt(s2, vec![else_guard]));
+                vec![self.node(&exits)]
+            }
+
+            Statement::While { body, .. } => {
+                let inv = self.node(&preds);
+                let guard = self.node(&[inv]);
+                let exits = self.stmt(body, vec![guard]);
+                self.connect(&exits, inv, true);
+                vec![self.node(&[inv])]
+            }
+
+            Statement::RepeatUntil { body, .. } => {
+                let first = self.stmt(body, preds);
+                let inv = self.node(&first);
+                let guard = self.node(&[inv]);
+                let exits = self.stmt(body, vec![guard]);
+                self.connect(&exits, inv, true);
+                vec![self.node(&[inv])]
+            }
+
+            Statement::For { body, .. } => {
+                let init = self.node(&preds);
+                let inv = self.node(&[init]);
+                let guard = self.node(&[inv]);
+                let exits = self.stmt(body, vec![guard]);
+                let step = self.node(&exits);
+                self.connect(&[step], inv, true);
+                vec![self.node(&[inv])]
+            }
+
+            _ => vec![self.node(&preds)],
+        }
+    }
+
+    // the node of the next program point, with an edge from each of `preds`
+    fn node(&mut self, preds: &[Node]) -> Node {
+        let node = Node::Point(self.next);
+        self.next += 1;
+        self.connect(preds, node, false);
+        node
+    }
+
+    fn connect(&mut self, preds: &[Node], to: Node, back: bool) {
+        for from in preds {
+            self.edges.push(Edge {
+                from: *from,
+                to,
+                back,
+            });
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use crate::parser::ast::*;
+use crate::parser::lexer::*;
+use crate::parser::span::*;
+use std::collections::VecDeque;
+
+const INDENT: &str = "    ";
+
+struct Printer<'a> {
+    source: &'a str,
+    index: LineIndex<'a>,
+    comments: VecDeque<Spanned<String>>,
+    lines: Vec<String>,
+    depth: usize,
+    // source line of the last printed item, 0 at the start of a block
+    last_line: usize,
+}
+
+pub fn format_source(source: &str) -> Result<String, Vec<Spanned<SyntaxError>>> {
+    let ast = parse(source)?;
+    let index = LineIndex::new(source);
+    let comments = Lexer::new(source)
+        .filter_map(|t| match t {
+            Ok((start, Token::Comment(text), end)) => {
+                Some(Spanned::new(text, index.span(start, end)))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut printer = Printer {
+        source,
+        index,
+        comments,
+        lines: vec![],
+        depth: 0,
+        last_line: 0,
+    };
+
+    printer.stmt(&ast);
+    printer.trailing(printer.end_line(&ast.span), usize::MAX);
+    printer.leading(usize::MAX);
+
+    let formatted = printer.lines.join("\n") + "\n";
+    debug_assert!(
+        parse(&formatted).is_ok(),
+        "formatted program does not parse"
+    );
+    Ok(formatted)
+}
+
+impl<'a> Printer<'a> {
+    fn stmt(&mut self, stmt: &Spanned<Statement>) {
+        let span = stmt.span;
+        self.leading(span.start);
+        match &stmt.node {
+            Statement::Chain(s1, s2) => {
+                self.stmt(s1);
+                self.append(";");
+                self.trailing(self.end_line(&s1.span), s2.span.start);
+                self.stmt(s2);
+            }
+
+            Statement::If { cond, s1, s2 } => {
+                let header = format!("if {} then", self.bexpr(cond));
+                self.open(header, &span, &cond.span, s1.span.start);
+                match (&s2.node, s2.span.start == s2.span.end) {
+                    // `if b then s end` carries an empty `skip` in place of the else branch
+                    (Statement::Skip, true) => self.block(s1, span.end),
+                    _ => {
+                        let limit = self.keyword(s1.span.end, Token::Else);
+                        self.block(s1, limit);
+                        self.close("else".to_string());
+                        self.block(s2, span.end);
+                    }
+                }
+                self.close("end".to_string());
+            }
+
+            Statement::While { cond, body, delay } => {
+                let header = format!("while {}{} do", self.bexpr(cond), pretty_delay(delay));
+                self.open(header, &span, &cond.span, body.span.start);
+                self.block(body, span.end);
+                self.close("done".to_string());
+            }
+
+            Statement::RepeatUntil { body, cond, delay } => {
+                self.open("repeat".to_string(), &span, &span, body.span.start);
+                let limit = self.keyword(body.span.end, Token::Until);
+                self.block(body, limit);
+                let footer = format!("until {}{}", self.bexpr(cond), pretty_delay(delay));
+                self.close(footer);
+            }
+
+            Statement::For {
+                var,
+                from,
+                to,
+                body,
+                delay,
+            } => {
+                let bounds = (self.aexpr(from), self.aexpr(to));
+                let header = format!(
+                    "for {} in [{}, {}]{} do",
+                    var,
+                    bounds.0,
+                    bounds.1,
+                    pretty_delay(delay)
+                );
+                self.open(header, &span, &to.span, body.span.start);
+                self.block(body, span.end);
+                self.close("done".to_string());
+            }
+
+            Statement::Assignment { var, val } => {
+                let text = format!("{} := {}", var, self.aexpr(val));
+                self.push(text, span.line);
+            }
+
+            Statement::CompoundAssignment { var, op, val } => {
+                let text = format!("{} {}= {}", var, op, self.aexpr(val));
+                self.push(text, span.line);
+            }
+
+            node => self.push(node.to_string(), span.line),
+        }
+        self.last_line = self.end_line(&span);
+    }
+
+    // expressions are printed from the source, so that literals keep the form
+    // they are written in and comments inside them stay in place
+    fn aexpr(&mut self, expr: &Spanned<ArithmeticExpr>) -> String {
+        let comments = self.inline(expr.span.start);
+        let text = match &expr.node {
+            ArithmeticExpr::Number(_) => self.text(&expr.span).to_string(),
+            ArithmeticExpr::Variable(var) => var.clone(),
+            ArithmeticExpr::PostIncrement(var) => format!("{}++", var),
+            ArithmeticExpr::PostDecrement(var) => format!("{}--", var),
+            ArithmeticExpr::Interval(a, b) => format!("[{}, {}]", self.aexpr(a), self.aexpr(b)),
+            // `-a` parses as `0 - a` with the zero spanning the minus
+            ArithmeticExpr::Sub(a, b) if self.text(&a.span) == "-" => {
+                let operand = self.wrap_aexpr(b, 3);
+                // `-n` would be a literal and `--` a decrement
+                match matches!(b.node, ArithmeticExpr::Number(_)) || operand.starts_with('-') {
+                    true => format!("-({})", operand),
+                    _ => format!("-{}", operand),
+                }
+            }
+            ArithmeticExpr::Add(a, b) => self.binary(a, "+", b, 1),
+            ArithmeticExpr::Sub(a, b) => self.binary(a, "-", b, 1),
+            ArithmeticExpr::Mul(a, b) => self.binary(a, "*", b, 2),
+            ArithmeticExpr::Div(a, b) => self.binary(a, "/", b, 2),
+        };
+        comments + &text
+    }
+
+    fn bexpr(&mut self, expr: &Spanned<BooleanExpr>) -> String {
+        let comments = self.inline(expr.span.start);
+        let text = match &expr.node {
+            BooleanExpr::True => "true".to_string(),
+            BooleanExpr::False => "false".to_string(),
+            BooleanExpr::Not(b) => format!("!{}", self.wrap_bexpr(b, 4)),
+            BooleanExpr::And(b1, b2) => {
+                let b1 = self.wrap_bexpr(b1, 2);
+                format!("{} && {}", b1, self.wrap_bexpr(b2, 3))
+            }
+            BooleanExpr::Or(b1, b2) => {
+                let b1 = self.wrap_bexpr(b1, 1);
+                format!("{} || {}", b1, self.wrap_bexpr(b2, 2))
+            }
+            BooleanExpr::NumEq(a1, a2) => self.comparison(a1, "==", a2),
+            BooleanExpr::NumNotEq(a1, a2) => self.comparison(a1, "!=", a2),
+            BooleanExpr::NumLt(a1, a2) => self.comparison(a1, "<", a2),
+            BooleanExpr::NumGt(a1, a2) => self.comparison(a1, ">", a2),
+            BooleanExpr::NumLtEq(a1, a2) => self.comparison(a1, "<=", a2),
+            BooleanExpr::NumGtEq(a1, a2) => self.comparison(a1, ">=", a2),
+        };
+        comments + &text
+    }
+
+    // left-associative, so the right operand binds tighter than the operator
+    fn binary(
+        &mut self,
+        a: &Spanned<ArithmeticExpr>,
+        op: &str,
+        b: &Spanned<ArithmeticExpr>,
+        precedence: u8,
+    ) -> String {
+        let a = self.wrap_aexpr(a, precedence);
+        format!("{} {} {}", a, op, self.wrap_aexpr(b, precedence + 1))
+    }
+
+    fn comparison(
+        &mut self,
+        a: &Spanned<ArithmeticExpr>,
+        op: &str,
+        b: &Spanned<ArithmeticExpr>,
+    ) -> String {
+        let a = self.aexpr(a);
+        format!("{} {} {}", a, op, self.aexpr(b))
+    }
+
+    fn wrap_aexpr(&mut self, expr: &Spanned<ArithmeticExpr>, min: u8) -> String {
+        // a zero written out keeps its subtraction, which binds as loosely as any
+        let precedence = match &expr.node {
+            ArithmeticExpr::Sub(a, _) if self.text(&a.span) != "-" => 1,
+            node => node.precedence(),
+        };
+        let text = self.aexpr(expr);
+        match precedence < min {
+            true => format!("({})", text),
+            _ => text,
+        }
+    }
+
+    fn wrap_bexpr(&mut self, expr: &Spanned<BooleanExpr>, min: u8) -> String {
+        let text = self.bexpr(expr);
+        match expr.precedence() < min {
+            true => format!("({})", text),
+            _ => text,
+        }
+    }
+
+    // the comments before `offset` inside an expression, a line comment ending
+    // its line with the rest of the expression indented below it
+    fn inline(&mut self, offset: usize) -> String {
+        let mut text = String::new();
+        while let Some(comment) = self.comments.front().filter(|c| c.span.start < offset) {
+            text.push_str(&comment.node);
+            match comment.node.starts_with("//") {
+                true => text.push_str(&format!("\n{}", INDENT.repeat(self.depth + 1))),
+                _ => text.push(' '),
+            }
+            self.comments.pop_front();
+        }
+        text
+    }
+
+    fn text(&self, span: &Span) -> &'a str {
+        &self.source[span.start..span.end]
+    }
+
+    fn open(&mut self, header: String, span: &Span, header_end: &Span, limit: usize) {
+        self.push(header, span.line);
+        self.trailing(self.end_line(header_end), limit);
+    }
+
+    fn close(&mut self, text: String) {
+        self.lines.push(INDENT.repeat(self.depth) + &text);
+    }
+
+    fn block(&mut self, body: &Spanned<Statement>, limit: usize) {
+        self.depth += 1;
+        self.last_line = 0;
+        self.stmt(body);
+        self.trailing(self.end_line(&body.span), limit);
+        self.leading(limit);
+        self.depth -= 1;
+    }
+
+    // prints the comments before `offset` on their own lines
+    fn leading(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.front().filter(|c| c.span.start < offset) {
+            let comment = comment.clone();
+            self.comments.pop_front();
+            self.push(comment.node, comment.span.line);
+            self.last_line = self.end_line(&comment.span);
+        }
+    }
+
+    // appends the comments starting on `line` before `limit` to the last printed line
+    fn trailing(&mut self, line: usize, limit: usize) {
+        while let Some(comment) = self
+            .comments
+            .front()
+            .filter(|c| c.span.line == line && c.span.start < limit)
+        {
+            let comment = comment.clone();
+            self.comments.pop_front();
+            self.append(&format!(" {}", comment.node));
+            self.last_line = self.end_line(&comment.span);
+        }
+    }
+
+    // keeps at most one of the blank lines separating two items
+    fn push(&mut self, text: String, line: usize) {
+        if self.last_line != 0 && line > self.last_line + 1 {
+            self.lines.push(String::new());
+        }
+        self.lines.push(INDENT.repeat(self.depth) + &text);
+        self.last_line = line;
+    }
+
+    fn append(&mut self, text: &str) {
+        if let Some(last) = self.lines.last_mut() {
+            last.push_str(text);
+        }
+    }
+
+    // offset of the first `keyword` token after `offset`
+    fn keyword(&self, offset: usize, keyword: Token) -> usize {
+        Lexer::new(&self.source[offset..])
+            .flatten()
+            .find(|(_, token, _)| *token == keyword)
+            .map_or(self.source.len(), |(start, _, _)| offset + start)
+    }
+
+    fn end_line(&self, span: &Span) -> usize {
+        self.index.position(span.end).0
+    }
+}
+
+pub fn pretty_delay(delay: &Option<i64>) -> String {
+    match delay {
+        Some(n) => format!(" @delay:{}", n),
+        None => String::new(),
+    }
+}
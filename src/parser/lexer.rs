@@ -0,0 +1,489 @@
+use crate::types::integer::*;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Identifier(String),
+    Number(Integer),
+    Comment(String),
+
+    // stands in for a lexical error so that the parser can recover from it
+    Invalid,
+
+    // keywords
+    Skip,
+    If,
+    Then,
+    Else,
+    End,
+    While,
+    Do,
+    Done,
+    Repeat,
+    Until,
+    For,
+    In,
+    True,
+    False,
+    NegInf,
+    PosInf,
+    Delay,
+
+    // punctuation
+    Assign,
+    AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+    Increment,
+    Decrement,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Eq,
+    NotEq,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Semicolon,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexicalError {
+    InvalidCharacter {
+        ch: char,
+        start: usize,
+        end: usize,
+    },
+    InvalidNumber {
+        literal: String,
+        start: usize,
+        end: usize,
+    },
+    UnterminatedComment {
+        start: usize,
+        end: usize,
+    },
+}
+
+pub struct Lexer<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    // the last token that is not a comment
+    last: Option<Token>,
+}
+
+const KEYWORDS: &[(&str, Token)] = &[
+    ("skip", Token::Skip),
+    ("if", Token::If),
+    ("then", Token::Then),
+    ("else", Token::Else),
+    ("end", Token::End),
+    ("while", Token::While),
+    ("do", Token::Do),
+    ("done", Token::Done),
+    ("repeat", Token::Repeat),
+    ("until", Token::Until),
+    ("for", Token::For),
+    ("in", Token::In),
+    ("true", Token::True),
+    ("false", Token::False),
+    ("neginf", Token::NegInf),
+    ("posinf", Token::PosInf),
+];
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Lexer {
+            source,
+            chars: source.char_indices().peekable(),
+            last: None,
+        }
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.source.len(), |(i, _)| *i)
+    }
+
+    fn next_is(&mut self, expected: char) -> bool {
+        self.chars.next_if(|(_, c)| *c == expected).is_some()
+    }
+
+    fn take_while(&mut self, start: usize, pred: impl Fn(char) -> bool) -> &'a str {
+        while self.chars.next_if(|(_, c)| pred(*c)).is_some() {}
+        let end = self.offset();
+        &self.source[start..end]
+    }
+
+    // a minus that cannot subtract from an operand before it is the sign of a
+    // literal right after it, so that the least value has a literal
+    fn is_sign(&mut self) -> bool {
+        let operand = matches!(
+            self.last,
+            Some(
+                Token::Identifier(_)
+                    | Token::Number(_)
+                    | Token::NegInf
+                    | Token::PosInf
+                    | Token::Increment
+                    | Token::Decrement
+                    | Token::RParen
+                    | Token::RBracket
+            )
+        );
+        !operand && matches!(self.chars.peek(), Some((_, '0'..='9')))
+    }
+
+    fn word(&mut self, start: usize) -> Token {
+        let word = self.take_while(start, |c| c.is_ascii_alphanumeric() || c == '_');
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == word)
+            .map(|(_, token)| token.clone())
+            .unwrap_or_else(|| Token::Identifier(word.to_string()))
+    }
+
+    fn number(&mut self, start: usize) -> Result<Token, LexicalError> {
+        let literal = self.take_while(start, |c| c.is_ascii_alphanumeric() || c == '_');
+        let end = start + literal.len();
+        let digits = literal.replace('_', "");
+        let (sign, digits) = match digits.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", &digits[..]),
+        };
+
+        let (radix, digits) = match digits.get(..2) {
+            Some("0x") | Some("0X") => (16, &digits[2..]),
+            Some("0b") | Some("0B") => (2, &digits[2..]),
+            _ => (10, digits),
+        };
+
+        // a literal out of the range of `i64` is invalid as well, rather than infinite
+        match i64::from_str_radix(&format!("{}{}", sign, digits), radix) {
+            Ok(n) => Ok(Token::Number(Integer::Value(n))),
+            Err(_) => Err(LexicalError::InvalidNumber {
+                literal: literal.to_string(),
+                start,
+                end,
+            }),
+        }
+    }
+
+    fn line_comment(&mut self, start: usize) -> Token {
+        let text = self.take_while(start, |c| c != '\n');
+        Token::Comment(text.to_string())
+    }
+
+    fn block_comment(&mut self, start: usize) -> Result<Token, LexicalError> {
+        loop {
+            match self.chars.next() {
+                Some((_, '*')) if self.next_is('/') => {
+                    let end = self.offset();
+                    return Ok(Token::Comment(self.source[start..end].to_string()));
+                }
+                Some(_) => continue,
+                None => {
+                    return Err(LexicalError::UnterminatedComment {
+                        start,
+                        end: self.source.len(),
+                    })
+                }
+            }
+        }
+    }
+
+    fn delay(&mut self, start: usize) -> Result<Token, LexicalError> {
+        let rest = &self.source[start..];
+        match rest.starts_with("@delay:") {
+            true => {
+                for _ in 1.."@delay:".len() {
+                    self.chars.next();
+                }
+                Ok(Token::Delay)
+            }
+            _ => Err(LexicalError::InvalidCharacter {
+                ch: '@',
+                start,
+                end: start + 1,
+            }),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<(usize, Token, usize), LexicalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+        let (start, c) = self.chars.next()?;
+        let token = match c {
+            'a'..='z' | 'A'..='Z' | '_' => Ok(self.word(start)),
+            '0'..='9' => self.number(start),
+            '-' if self.is_sign() => self.number(start),
+            '/' if self.next_is('/') => Ok(self.line_comment(start)),
+            '/' if self.next_is('*') => self.block_comment(start),
+            '@' => self.delay(start),
+
+            ':' if self.next_is('=') => Ok(Token::Assign),
+            '+' if self.next_is('=') => Ok(Token::AddAssign),
+            '-' if self.next_is('=') => Ok(Token::SubAssign),
+            '*' if self.next_is('=') => Ok(Token::MulAssign),
+            '/' if self.next_is('=') => Ok(Token::DivAssign),
+            '+' if self.next_is('+') => Ok(Token::Increment),
+            '-' if self.next_is('-') => Ok(Token::Decrement),
+            '=' if self.next_is('=') => Ok(Token::Eq),
+            '!' if self.next_is('=') => Ok(Token::NotEq),
+            '<' if self.next_is('=') => Ok(Token::LtEq),
+            '>' if self.next_is('=') => Ok(Token::GtEq),
+            '&' if self.next_is('&') => Ok(Token::And),
+            '|' if self.next_is('|') => Ok(Token::Or),
+
+            '+' => Ok(Token::Plus),
+            '-' => Ok(Token::Minus),
+            '*' => Ok(Token::Star),
+            '/' => Ok(Token::Slash),
+            '<' => Ok(Token::Lt),
+            '>' => Ok(Token::Gt),
+            '!' => Ok(Token::Not),
+            '(' => Ok(Token::LParen),
+            ')' => Ok(Token::RParen),
+            '[' => Ok(Token::LBracket),
+            ']' => Ok(Token::RBracket),
+            ',' => Ok(Token::Comma),
+            ';' => Ok(Token::Semicolon),
+
+            _ => Err(LexicalError::InvalidCharacter {
+                ch: c,
+                start,
+                end: start + c.len_utf8(),
+            }),
+        };
+
+        if let Ok(token) = &token {
+            if !matches!(token, Token::Comment(_)) {
+                self.last = Some(token.clone());
+            }
+        }
+        Some(token.map(|token| (start, token, self.offset())))
+    }
+}
+
+impl Token {
+    pub fn is_keyword(&self) -> bool {
+        *self == Token::Delay || KEYWORDS.iter().any(|(_, keyword)| keyword == self)
+    }
+}
+
+impl LexicalError {
+    pub fn location(&self) -> (usize, usize) {
+        match self {
+            LexicalError::InvalidCharacter { start, end, .. }
+            | LexicalError::InvalidNumber { start, end, .. }
+            | LexicalError::UnterminatedComment { start, end } => (*start, *end),
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Identifier(x) => write!(f, "{}", x),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Comment(c) => write!(f, "{}", c),
+            Token::Invalid => write!(f, "<invalid>"),
+            Token::Delay => write!(f, "@delay:"),
+            Token::Assign => write!(f, ":="),
+            Token::AddAssign => write!(f, "+="),
+            Token::SubAssign => write!(f, "-="),
+            Token::MulAssign => write!(f, "*="),
+            Token::DivAssign => write!(f, "/="),
+            Token::Increment => write!(f, "++"),
+            Token::Decrement => write!(f, "--"),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::Slash => write!(f, "/"),
+            Token::Eq => write!(f, "=="),
+            Token::NotEq => write!(f, "!="),
+            Token::Lt => write!(f, "<"),
+            Token::Gt => write!(f, ">"),
+            Token::LtEq => write!(f, "<="),
+            Token::GtEq => write!(f, ">="),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Not => write!(f, "!"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::LBracket => write!(f, "["),
+            Token::RBracket => write!(f, "]"),
+            Token::Comma => write!(f, ","),
+            Token::Semicolon => write!(f, ";"),
+            keyword => {
+                let (word, _) = KEYWORDS.iter().find(|(_, k)| k == keyword).unwrap();
+                write!(f, "{}", word)
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexicalError::InvalidCharacter { ch, .. } => write!(f, "invalid character `{}`", ch),
+            LexicalError::InvalidNumber { literal, .. } => {
+                write!(f, "invalid number literal `{}`", literal)
+            }
+            LexicalError::UnterminatedComment { .. } => write!(f, "unterminated block comment"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(source: &str) -> Vec<Result<Token, LexicalError>> {
+        Lexer::new(source)
+            .map(|t| t.map(|(_, token, _)| token))
+            .collect()
+    }
+
+    fn invalid_number(literal: &str, start: usize) -> Result<Token, LexicalError> {
+        Err(LexicalError::InvalidNumber {
+            literal: literal.to_string(),
+            start,
+            end: start + literal.len(),
+        })
+    }
+
+    #[test]
+    fn numbers_in_every_radix() {
+        assert_eq!(
+            lex("42 0x2A 0b101010 1_000"),
+            vec![
+                Ok(Token::Number(Integer::Value(42))),
+                Ok(Token::Number(Integer::Value(42))),
+                Ok(Token::Number(Integer::Value(42))),
+                Ok(Token::Number(Integer::Value(1000))),
+            ]
+        );
+    }
+
+    #[test]
+    fn largest_number() {
+        assert_eq!(
+            lex("9223372036854775807"),
+            vec![Ok(Token::Number(Integer::Value(i64::MAX)))]
+        );
+    }
+
+    #[test]
+    fn signed_numbers() {
+        assert_eq!(
+            lex("-9223372036854775808 (-0x10) [-1, x-1] 2 -3"),
+            vec![
+                Ok(Token::Number(Integer::Value(i64::MIN))),
+                Ok(Token::LParen),
+                Ok(Token::Number(Integer::Value(-16))),
+                Ok(Token::RParen),
+                Ok(Token::LBracket),
+                Ok(Token::Number(Integer::Value(-1))),
+                Ok(Token::Comma),
+                Ok(Token::Identifier("x".to_string())),
+                Ok(Token::Minus),
+                Ok(Token::Number(Integer::Value(1))),
+                Ok(Token::RBracket),
+                Ok(Token::Number(Integer::Value(2))),
+                Ok(Token::Minus),
+                Ok(Token::Number(Integer::Value(3))),
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_range_number() {
+        assert_eq!(
+            lex("x := 9223372036854775808"),
+            vec![
+                Ok(Token::Identifier("x".to_string())),
+                Ok(Token::Assign),
+                invalid_number("9223372036854775808", 5),
+            ]
+        );
+        assert_eq!(
+            lex("0x8000_0000_0000_0000"),
+            vec![invalid_number("0x8000_0000_0000_0000", 0)]
+        );
+    }
+
+    #[test]
+    fn malformed_number() {
+        assert_eq!(lex("12ab"), vec![invalid_number("12ab", 0)]);
+        assert_eq!(lex("0b102"), vec![invalid_number("0b102", 0)]);
+        assert_eq!(lex("0x"), vec![invalid_number("0x", 0)]);
+    }
+
+    #[test]
+    fn keywords_and_identifiers() {
+        assert_eq!(
+            lex("while done_ do"),
+            vec![
+                Ok(Token::While),
+                Ok(Token::Identifier("done_".to_string())),
+                Ok(Token::Do),
+            ]
+        );
+    }
+
+    #[test]
+    fn two_character_operators() {
+        assert_eq!(
+            lex("x++ - -y--"),
+            vec![
+                Ok(Token::Identifier("x".to_string())),
+                Ok(Token::Increment),
+                Ok(Token::Minus),
+                Ok(Token::Minus),
+                Ok(Token::Identifier("y".to_string())),
+                Ok(Token::Decrement),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_and_delay() {
+        assert_eq!(
+            lex("// line\n/* block */ @delay:"),
+            vec![
+                Ok(Token::Comment("// line".to_string())),
+                Ok(Token::Comment("/* block */".to_string())),
+                Ok(Token::Delay),
+            ]
+        );
+        assert_eq!(
+            lex("/* open"),
+            vec![Err(LexicalError::UnterminatedComment { start: 0, end: 7 })]
+        );
+        assert_eq!(
+            lex("@"),
+            vec![Err(LexicalError::InvalidCharacter {
+                ch: '@',
+                start: 0,
+                end: 1
+            })]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::parser::ast::*;
+use crate::parser::span::*;
+use crate::parser::visit::*;
+use crate::types::integer::*;
+
+// rewrites the surface constructs (`for`, `repeat`, `x++`, `x op= a`) into the core
+// statements evaluated by the semantics, keeping one program point per surface point
+struct Lowering;
+
+pub fn lower(stmt: Spanned<Statement>) -> Spanned<Statement> {
+    Lowering.fold_stmt(stmt)
+}
+
+impl Fold for Lowering {
+    fn fold_stmt(&mut self, stmt: Spanned<Statement>) -> Spanned<Statement> {
+        let stmt = walk_fold_stmt(self, stmt);
+        let span = stmt.span;
+
+        let node = match stmt.node.clone() {
+            Statement::CompoundAssignment { var, op, val } => update(var, op, *val, span),
+            Statement::Increment(var) => update(var, ArithmeticOp::Add, one(span), span),
+            Statement::Decrement(var) => update(var, ArithmeticOp::Sub, one(span), span),
+
+            Statement::RepeatUntil { body, cond, .. } => Statement::Chain(
+                body.clone(),
+                Spanned::boxed(
+                    Statement::While {
+                        cond: Box::new(cond.map(|c| c.negate())),
+                        body,
+                        delay: Some(get_loop_delay(&stmt)),
+                    },
+                    span,
+                ),
+            ),
+
+            Statement::For {
+                var,
+                from,
+                to,
+                body,
+                ..
+            } => {
+                let body_span = body.span;
+                let step = Spanned::boxed(
+                    update(var.clone(), ArithmeticOp::Add, one(span), span),
+                    span,
+                );
+
+                Statement::Chain(
+                    Spanned::boxed(
+                        Statement::Assignment {
+                            var: var.clone(),
+                            val: from,
+                        },
+                        span,
+                    ),
+                    Spanned::boxed(
+                        Statement::While {
+                            cond: Box::new(for_guard(&var, &to, span)),
+                            body: Spanned::boxed(Statement::Chain(body, step), body_span),
+                            delay: Some(get_loop_delay(&stmt)),
+                        },
+                        span,
+                    ),
+                )
+            }
+
+            _ => return stmt,
+        };
+
+        Spanned::new(node, span)
+    }
+}
+
+pub fn for_guard(
+    var: &Identifier,
+    to: &Spanned<ArithmeticExpr>,
+    span: Span,
+) -> Spanned<BooleanExpr> {
+    Spanned::new(
+        BooleanExpr::NumLtEq(
+            Spanned::boxed(ArithmeticExpr::Variable(var.clone()), span),
+            Box::new(to.clone()),
+        ),
+        span,
+    )
+}
+
+fn update(
+    var: Identifier,
+    op: ArithmeticOp,
+    val: Spanned<ArithmeticExpr>,
+    span: Span,
+) -> Statement {
+    let current = Spanned::boxed(ArithmeticExpr::Variable(var.clone()), span);
+    Statement::Assignment {
+        var,
+        val: Spanned::boxed(op.apply(current, Box::new(val)), span),
+    }
+}
+
+fn one(span: Span) -> Spanned<ArithmeticExpr> {
+    Spanned::new(ArithmeticExpr::Number(ONE), span)
+}
@@ -1,2 +1,8 @@
 pub mod ast;
+pub mod cfg;
+pub mod format;
+pub mod lexer;
+pub mod lower;
 pub mod program_point;
+pub mod span;
+pub mod visit;
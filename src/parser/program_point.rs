@@ -1,17 +1,91 @@
 use crate::parser::ast::*;
+use crate::parser::lower::*;
+use crate::parser::span::*;
+use crate::parser::visit::*;
 use std::fmt::{self};
 
+#[derive(Debug, Clone)]
 pub enum ProgramPoint {
-    Skip(Statement),
-    Assignment(Statement),
+    Skip(Spanned<Statement>),
+    Assignment(Spanned<Statement>),
 
-    IfGuard(BooleanExpr),
-    ElseGuard(BooleanExpr),
-    EndIf,
+    IfGuard(Spanned<BooleanExpr>),
+    ElseGuard(Spanned<BooleanExpr>),
+    EndIf(Span),
 
-    WhileInv { delay: i64 },
-    WhileGuard(BooleanExpr),
-    EndWhile(BooleanExpr),
+    WhileInv {
+        delay: i64,
+        span: Span,
+    },
+    WhileGuard(Spanned<BooleanExpr>),
+    EndWhile(Spanned<BooleanExpr>),
+
+    RepeatInv {
+        delay: i64,
+        span: Span,
+    },
+    RepeatGuard(Spanned<BooleanExpr>),
+    EndRepeat(Spanned<BooleanExpr>),
+
+    ForInit {
+        var: Identifier,
+        from: Spanned<ArithmeticExpr>,
+        to: Spanned<ArithmeticExpr>,
+        span: Span,
+    },
+    ForInv {
+        delay: i64,
+        span: Span,
+    },
+    ForGuard(Spanned<BooleanExpr>),
+    ForStep {
+        var: Identifier,
+        span: Span,
+    },
+    EndFor(Spanned<BooleanExpr>),
+}
+
+impl ProgramPoint {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProgramPoint::Skip(_) => "skip",
+            ProgramPoint::Assignment(_) => "assignment",
+            ProgramPoint::IfGuard(_) => "if-guard",
+            ProgramPoint::ElseGuard(_) => "else-guard",
+            ProgramPoint::EndIf(_) => "end-if",
+            ProgramPoint::WhileInv { .. } => "while-inv",
+            ProgramPoint::WhileGuard(_) => "while-guard",
+            ProgramPoint::EndWhile(_) => "end-while",
+            ProgramPoint::RepeatInv { .. } => "repeat-inv",
+            ProgramPoint::RepeatGuard(_) => "repeat-guard",
+            ProgramPoint::EndRepeat(_) => "end-repeat",
+            ProgramPoint::ForInit { .. } => "for-init",
+            ProgramPoint::ForInv { .. } => "for-inv",
+            ProgramPoint::ForGuard(_) => "for-guard",
+            ProgramPoint::ForStep { .. } => "for-step",
+            ProgramPoint::EndFor(_) => "end-for",
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ProgramPoint::Skip(s) | ProgramPoint::Assignment(s) => s.span,
+            ProgramPoint::IfGuard(b)
+            | ProgramPoint::ElseGuard(b)
+            | ProgramPoint::WhileGuard(b)
+            | ProgramPoint::EndWhile(b)
+            | ProgramPoint::RepeatGuard(b)
+            | ProgramPoint::EndRepeat(b)
+            | ProgramPoint::ForGuard(b)
+            | ProgramPoint::EndFor(b) => b.span,
+            ProgramPoint::EndIf(span)
+            | ProgramPoint::WhileInv { span, .. }
+            | ProgramPoint::RepeatInv { span, .. }
+            | ProgramPoint::ForInit { span, .. }
+            | ProgramPoint::ForInv { span, .. }
+            | ProgramPoint::ForStep { span, .. } => *span,
+        }
+    }
 }
 
 impl fmt::Display for ProgramPoint {
@@ -22,56 +96,111 @@ impl fmt::Display for ProgramPoint {
 
             ProgramPoint::IfGuard(b) => write!(f, "[if-guard] {}", b),
             ProgramPoint::ElseGuard(b) => write!(f, "[else-guard] {}", b),
-            ProgramPoint::EndIf => write!(f, "[end-if]"),
+            ProgramPoint::EndIf(_) => write!(f, "[end-if]"),
 
-            ProgramPoint::WhileInv { delay } => write!(f, "[while-inv] @delay:{}", delay),
+            ProgramPoint::WhileInv { delay, .. } => write!(f, "[while-inv] @delay:{}", delay),
             ProgramPoint::WhileGuard(b) => write!(f, "[while-guard] {}", b),
             ProgramPoint::EndWhile(b) => write!(f, "[end-while] {}", b),
+
+            ProgramPoint::RepeatInv { delay, .. } => write!(f, "[repeat-inv] @delay:{}", delay),
+            ProgramPoint::RepeatGuard(b) => write!(f, "[repeat-guard] {}", b),
+            ProgramPoint::EndRepeat(b) => write!(f, "[end-repeat] {}", b),
+
+            ProgramPoint::ForInit { var, from, to, .. } => {
+                write!(f, "[for-init] {} in [{}, {}]", var, from, to)
+            }
+            ProgramPoint::ForInv { delay, .. } => write!(f, "[for-inv] @delay:{}", delay),
+            ProgramPoint::ForGuard(b) => write!(f, "[for-guard] {}", b),
+            ProgramPoint::ForStep { var, .. } => write!(f, "[for-step] {}++", var),
+            ProgramPoint::EndFor(b) => write!(f, "[end-for] {}", b),
         }
     }
 }
 
-pub fn get_program_points(stmt: Statement) -> Vec<ProgramPoint> {
-    use Statement::*;
-
-    match stmt.clone() {
-        Skip => vec![ProgramPoint::Skip(stmt)],
-        Assignment { var: _, val: _ } => vec![ProgramPoint::Assignment(stmt)],
-        Chain(s1, s2) => concat(vec![get_program_points(*s1), get_program_points(*s2)]),
-
-        If { cond, s1, s2 } => concat(vec![
-            vec![ProgramPoint::IfGuard(*cond.clone())],
-            get_program_points(*s1),
-            vec![ProgramPoint::ElseGuard(cond.negate())],
-            get_program_points(*s2),
-            vec![ProgramPoint::EndIf],
-        ]),
-
-        While { cond, body, .. } => concat(vec![
-            vec![ProgramPoint::WhileInv {
-                delay: get_loop_delay(&stmt),
-            }],
-            vec![ProgramPoint::WhileGuard(*cond.clone())],
-            get_program_points(*body),
-            vec![ProgramPoint::EndWhile(cond.negate())],
-        ]),
-
-        RepeatUntil { body, cond, .. } => concat(vec![
-            get_program_points(*body.clone()),
-            vec![ProgramPoint::WhileInv {
-                delay: get_loop_delay(&stmt),
-            }],
-            vec![ProgramPoint::WhileGuard(*cond.clone())],
-            get_program_points(*body),
-            vec![ProgramPoint::EndWhile(cond.negate())],
-        ]),
-    }
+pub fn get_program_points(stmt: Spanned<Statement>) -> Vec<ProgramPoint> {
+    let mut collector = ProgramPoints(vec![]);
+    collector.visit_stmt(&stmt);
+    collector.0
 }
 
-fn concat(vs: Vec<Vec<ProgramPoint>>) -> Vec<ProgramPoint> {
-    let mut new: Vec<ProgramPoint> = vec![];
-    for v in vs {
-        new.extend(v);
+// the points of a statement in the order the semantics reach them, the body of a
+// repeat appearing before its invariant and again after its guard
+struct ProgramPoints(Vec<ProgramPoint>);
+
+impl Visitor for ProgramPoints {
+    fn visit_stmt(&mut self, stmt: &Spanned<Statement>) {
+        use Statement::*;
+
+        let span = stmt.span;
+        match &stmt.node {
+            Skip => self.0.push(ProgramPoint::Skip(stmt.clone())),
+            Error(_) => (),
+            Assignment { .. } | CompoundAssignment { .. } | Increment(_) | Decrement(_) => {
+                self.0.push(ProgramPoint::Assignment(stmt.clone()))
+            }
+            Chain(s1, s2) => {
+                self.visit_stmt(s1);
+                self.visit_stmt(s2);
+            }
+
+            If { cond, s1, s2 } => {
+                self.0.push(ProgramPoint::IfGuard(*cond.clone()));
+                self.visit_stmt(s1);
+                self.0
+                    .push(ProgramPoint::ElseGuard(cond.map(|c| c.negate())));
+                self.visit_stmt(s2);
+                self.0.push(ProgramPoint::EndIf(span));
+            }
+
+            While { cond, body, .. } => {
+                self.0.push(ProgramPoint::WhileInv {
+                    delay: get_loop_delay(stmt),
+                    span,
+                });
+                self.0.push(ProgramPoint::WhileGuard(*cond.clone()));
+                self.visit_stmt(body);
+                self.0
+                    .push(ProgramPoint::EndWhile(cond.map(|c| c.negate())));
+            }
+
+            RepeatUntil { body, cond, .. } => {
+                self.visit_stmt(body);
+                self.0.push(ProgramPoint::RepeatInv {
+                    delay: get_loop_delay(stmt),
+                    span,
+                });
+                self.0
+                    .push(ProgramPoint::RepeatGuard(cond.map(|c| c.negate())));
+                self.visit_stmt(body);
+                self.0.push(ProgramPoint::EndRepeat(*cond.clone()));
+            }
+
+            For {
+                var,
+                from,
+                to,
+                body,
+                ..
+            } => {
+                let guard = for_guard(var, to, span);
+                self.0.push(ProgramPoint::ForInit {
+                    var: var.clone(),
+                    from: *from.clone(),
+                    to: *to.clone(),
+                    span,
+                });
+                self.0.push(ProgramPoint::ForInv {
+                    delay: get_loop_delay(stmt),
+                    span,
+                });
+                self.0.push(ProgramPoint::ForGuard(guard.clone()));
+                self.visit_stmt(body);
+                self.0.push(ProgramPoint::ForStep {
+                    var: var.clone(),
+                    span,
+                });
+                self.0.push(ProgramPoint::EndFor(guard.map(|g| g.negate())));
+            }
+        }
     }
-    new
 }
@@ -0,0 +1,148 @@
+use serde::Serialize;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl Span {
+    pub fn merge(&self, other: &Span) -> Span {
+        match self.start <= other.start {
+            true => Span {
+                end: self.end.max(other.end),
+                ..*self
+            },
+            _ => other.merge(self),
+        }
+    }
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned { node, span }
+    }
+
+    pub fn boxed(node: T, span: Span) -> Box<Self> {
+        Box::new(Spanned::new(node, span))
+    }
+
+    pub fn map<U>(&self, f: impl FnOnce(&T) -> U) -> Spanned<U> {
+        Spanned::new(f(&self.node), self.span)
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.node
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Spanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.node)
+    }
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        LineIndex {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let line_start = self.line_starts[line];
+        let column = self.source[line_start..offset].chars().count();
+        (line + 1, column + 1)
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let (line, column) = self.position(start);
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    pub fn text(&self, start: usize, end: usize) -> &'a str {
+        &self.source[start.min(end)..end]
+    }
+
+    pub fn line_text(&self, line: usize) -> &'a str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .copied()
+            .unwrap_or(self.source.len());
+        self.source[start..end].trim_end_matches(['\n', '\r'])
+    }
+}
+
+// --- diagnostics
+
+pub fn location(file: &str, span: &Span) -> String {
+    format!("{}:{}:{}", file, span.line, span.column)
+}
+
+pub fn render_diagnostic(file: &str, source: &str, span: &Span, message: &str) -> String {
+    let index = LineIndex::new(source);
+    let text = index.line_text(span.line);
+    let gutter = " ".repeat(span.line.to_string().len());
+
+    let line_end = source[span.start..]
+        .find('\n')
+        .map_or(source.len(), |i| span.start + i);
+    let width = source[span.start..span.end.clamp(span.start, line_end)]
+        .chars()
+        .count()
+        .max(1);
+
+    format!(
+        "{}\n{}--> {}\n{} |\n{} | {}\n{} | {}{}",
+        message,
+        gutter,
+        location(file, span),
+        gutter,
+        span.line,
+        text,
+        gutter,
+        " ".repeat(span.column - 1),
+        "^".repeat(width)
+    )
+}
@@ -0,0 +1,388 @@
+use crate::parser::ast::*;
+use crate::parser::span::*;
+
+// --- read-only traversal
+
+pub trait Visitor {
+    fn visit_stmt(&mut self, stmt: &Spanned<Statement>) {
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_aexpr(&mut self, expr: &Spanned<ArithmeticExpr>) {
+        walk_aexpr(self, expr)
+    }
+
+    fn visit_bexpr(&mut self, expr: &Spanned<BooleanExpr>) {
+        walk_bexpr(self, expr)
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Statement) {
+    match stmt {
+        Statement::Skip
+        | Statement::Error(_)
+        | Statement::Increment(_)
+        | Statement::Decrement(_) => {}
+        Statement::Chain(s1, s2) => {
+            visitor.visit_stmt(s1);
+            visitor.visit_stmt(s2);
+        }
+        Statement::Assignment { val, .. } | Statement::CompoundAssignment { val, .. } => {
+            visitor.visit_aexpr(val)
+        }
+        Statement::If { cond, s1, s2 } => {
+            visitor.visit_bexpr(cond);
+            visitor.visit_stmt(s1);
+            visitor.visit_stmt(s2);
+        }
+        Statement::While { cond, body, .. } => {
+            visitor.visit_bexpr(cond);
+            visitor.visit_stmt(body);
+        }
+        Statement::RepeatUntil { body, cond, .. } => {
+            visitor.visit_stmt(body);
+            visitor.visit_bexpr(cond);
+        }
+        Statement::For { from, to, body, .. } => {
+            visitor.visit_aexpr(from);
+            visitor.visit_aexpr(to);
+            visitor.visit_stmt(body);
+        }
+    }
+}
+
+pub fn walk_aexpr<V: Visitor + ?Sized>(visitor: &mut V, expr: &ArithmeticExpr) {
+    match expr {
+        ArithmeticExpr::Number(_)
+        | ArithmeticExpr::Variable(_)
+        | ArithmeticExpr::PostIncrement(_)
+        | ArithmeticExpr::PostDecrement(_) => (),
+        ArithmeticExpr::Interval(a1, a2)
+        | ArithmeticExpr::Add(a1, a2)
+        | ArithmeticExpr::Sub(a1, a2)
+        | ArithmeticExpr::Mul(a1, a2)
+        | ArithmeticExpr::Div(a1, a2) => {
+            visitor.visit_aexpr(a1);
+            visitor.visit_aexpr(a2);
+        }
+    }
+}
+
+pub fn walk_bexpr<V: Visitor + ?Sized>(visitor: &mut V, expr: &BooleanExpr) {
+    match expr {
+        BooleanExpr::True | BooleanExpr::False => (),
+        BooleanExpr::Not(b) => visitor.visit_bexpr(b),
+        BooleanExpr::And(b1, b2) | BooleanExpr::Or(b1, b2) => {
+            visitor.visit_bexpr(b1);
+            visitor.visit_bexpr(b2);
+        }
+        BooleanExpr::NumEq(a1, a2)
+        | BooleanExpr::NumNotEq(a1, a2)
+        | BooleanExpr::NumLt(a1, a2)
+        | BooleanExpr::NumGt(a1, a2)
+        | BooleanExpr::NumLtEq(a1, a2)
+        | BooleanExpr::NumGtEq(a1, a2) => {
+            visitor.visit_aexpr(a1);
+            visitor.visit_aexpr(a2);
+        }
+    }
+}
+
+// --- in-place traversal
+
+pub trait VisitorMut {
+    fn visit_stmt_mut(&mut self, stmt: &mut Spanned<Statement>) {
+        walk_stmt_mut(self, stmt)
+    }
+
+    fn visit_aexpr_mut(&mut self, expr: &mut Spanned<ArithmeticExpr>) {
+        walk_aexpr_mut(self, expr)
+    }
+
+    fn visit_bexpr_mut(&mut self, expr: &mut Spanned<BooleanExpr>) {
+        walk_bexpr_mut(self, expr)
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Statement) {
+    match stmt {
+        Statement::Skip
+        | Statement::Error(_)
+        | Statement::Increment(_)
+        | Statement::Decrement(_) => {}
+        Statement::Chain(s1, s2) => {
+            visitor.visit_stmt_mut(s1);
+            visitor.visit_stmt_mut(s2);
+        }
+        Statement::Assignment { val, .. } | Statement::CompoundAssignment { val, .. } => {
+            visitor.visit_aexpr_mut(val)
+        }
+        Statement::If { cond, s1, s2 } => {
+            visitor.visit_bexpr_mut(cond);
+            visitor.visit_stmt_mut(s1);
+            visitor.visit_stmt_mut(s2);
+        }
+        Statement::While { cond, body, .. } => {
+            visitor.visit_bexpr_mut(cond);
+            visitor.visit_stmt_mut(body);
+        }
+        Statement::RepeatUntil { body, cond, .. } => {
+            visitor.visit_stmt_mut(body);
+            visitor.visit_bexpr_mut(cond);
+        }
+        Statement::For { from, to, body, .. } => {
+            visitor.visit_aexpr_mut(from);
+            visitor.visit_aexpr_mut(to);
+            visitor.visit_stmt_mut(body);
+        }
+    }
+}
+
+pub fn walk_aexpr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut ArithmeticExpr) {
+    match expr {
+        ArithmeticExpr::Number(_)
+        | ArithmeticExpr::Variable(_)
+        | ArithmeticExpr::PostIncrement(_)
+        | ArithmeticExpr::PostDecrement(_) => (),
+        ArithmeticExpr::Interval(a1, a2)
+        | ArithmeticExpr::Add(a1, a2)
+        | ArithmeticExpr::Sub(a1, a2)
+        | ArithmeticExpr::Mul(a1, a2)
+        | ArithmeticExpr::Div(a1, a2) => {
+            visitor.visit_aexpr_mut(a1);
+            visitor.visit_aexpr_mut(a2);
+        }
+    }
+}
+
+pub fn walk_bexpr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut BooleanExpr) {
+    match expr {
+        BooleanExpr::True | BooleanExpr::False => (),
+        BooleanExpr::Not(b) => visitor.visit_bexpr_mut(b),
+        BooleanExpr::And(b1, b2) | BooleanExpr::Or(b1, b2) => {
+            visitor.visit_bexpr_mut(b1);
+            visitor.visit_bexpr_mut(b2);
+        }
+        BooleanExpr::NumEq(a1, a2)
+        | BooleanExpr::NumNotEq(a1, a2)
+        | BooleanExpr::NumLt(a1, a2)
+        | BooleanExpr::NumGt(a1, a2)
+        | BooleanExpr::NumLtEq(a1, a2)
+        | BooleanExpr::NumGtEq(a1, a2) => {
+            visitor.visit_aexpr_mut(a1);
+            visitor.visit_aexpr_mut(a2);
+        }
+    }
+}
+
+// --- rebuilding traversal
+
+pub trait Fold {
+    fn fold_stmt(&mut self, stmt: Spanned<Statement>) -> Spanned<Statement> {
+        walk_fold_stmt(self, stmt)
+    }
+
+    fn fold_aexpr(&mut self, expr: Spanned<ArithmeticExpr>) -> Spanned<ArithmeticExpr> {
+        walk_fold_aexpr(self, expr)
+    }
+
+    fn fold_bexpr(&mut self, expr: Spanned<BooleanExpr>) -> Spanned<BooleanExpr> {
+        walk_fold_bexpr(self, expr)
+    }
+}
+
+pub fn walk_fold_stmt<F: Fold + ?Sized>(
+    folder: &mut F,
+    stmt: Spanned<Statement>,
+) -> Spanned<Statement> {
+    let node = match stmt.node {
+        Statement::Chain(s1, s2) => {
+            let s1 = Box::new(folder.fold_stmt(*s1));
+            Statement::Chain(s1, Box::new(folder.fold_stmt(*s2)))
+        }
+        Statement::Assignment { var, val } => Statement::Assignment {
+            var,
+            val: Box::new(folder.fold_aexpr(*val)),
+        },
+        Statement::CompoundAssignment { var, op, val } => Statement::CompoundAssignment {
+            var,
+            op,
+            val: Box::new(folder.fold_aexpr(*val)),
+        },
+        Statement::If { cond, s1, s2 } => Statement::If {
+            cond: Box::new(folder.fold_bexpr(*cond)),
+            s1: Box::new(folder.fold_stmt(*s1)),
+            s2: Box::new(folder.fold_stmt(*s2)),
+        },
+        Statement::While { cond, body, delay } => Statement::While {
+            cond: Box::new(folder.fold_bexpr(*cond)),
+            body: Box::new(folder.fold_stmt(*body)),
+            delay,
+        },
+        Statement::RepeatUntil { body, cond, delay } => Statement::RepeatUntil {
+            body: Box::new(folder.fold_stmt(*body)),
+            cond: Box::new(folder.fold_bexpr(*cond)),
+            delay,
+        },
+        Statement::For {
+            var,
+            from,
+            to,
+            body,
+            delay,
+        } => Statement::For {
+            var,
+            from: Box::new(folder.fold_aexpr(*from)),
+            to: Box::new(folder.fold_aexpr(*to)),
+            body: Box::new(folder.fold_stmt(*body)),
+            delay,
+        },
+        node => node,
+    };
+
+    Spanned::new(node, stmt.span)
+}
+
+pub fn walk_fold_aexpr<F: Fold + ?Sized>(
+    folder: &mut F,
+    expr: Spanned<ArithmeticExpr>,
+) -> Spanned<ArithmeticExpr> {
+    let node = match expr.node {
+        ArithmeticExpr::Interval(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            ArithmeticExpr::Interval(a1, a2)
+        }
+        ArithmeticExpr::Add(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            ArithmeticExpr::Add(a1, a2)
+        }
+        ArithmeticExpr::Sub(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            ArithmeticExpr::Sub(a1, a2)
+        }
+        ArithmeticExpr::Mul(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            ArithmeticExpr::Mul(a1, a2)
+        }
+        ArithmeticExpr::Div(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            ArithmeticExpr::Div(a1, a2)
+        }
+        node => node,
+    };
+
+    Spanned::new(node, expr.span)
+}
+
+pub fn walk_fold_bexpr<F: Fold + ?Sized>(
+    folder: &mut F,
+    expr: Spanned<BooleanExpr>,
+) -> Spanned<BooleanExpr> {
+    let node = match expr.node {
+        BooleanExpr::Not(b) => BooleanExpr::Not(Box::new(folder.fold_bexpr(*b))),
+        BooleanExpr::And(b1, b2) => {
+            let b1 = Box::new(folder.fold_bexpr(*b1));
+            BooleanExpr::And(b1, Box::new(folder.fold_bexpr(*b2)))
+        }
+        BooleanExpr::Or(b1, b2) => {
+            let b1 = Box::new(folder.fold_bexpr(*b1));
+            BooleanExpr::Or(b1, Box::new(folder.fold_bexpr(*b2)))
+        }
+        BooleanExpr::NumEq(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            BooleanExpr::NumEq(a1, a2)
+        }
+        BooleanExpr::NumNotEq(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            BooleanExpr::NumNotEq(a1, a2)
+        }
+        BooleanExpr::NumLt(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            BooleanExpr::NumLt(a1, a2)
+        }
+        BooleanExpr::NumGt(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            BooleanExpr::NumGt(a1, a2)
+        }
+        BooleanExpr::NumLtEq(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            BooleanExpr::NumLtEq(a1, a2)
+        }
+        BooleanExpr::NumGtEq(a1, a2) => {
+            let (a1, a2) = fold_operands(folder, *a1, *a2);
+            BooleanExpr::NumGtEq(a1, a2)
+        }
+        node => node,
+    };
+
+    Spanned::new(node, expr.span)
+}
+
+type Operands = (Box<Spanned<ArithmeticExpr>>, Box<Spanned<ArithmeticExpr>>);
+
+fn fold_operands<F: Fold + ?Sized>(
+    folder: &mut F,
+    a1: Spanned<ArithmeticExpr>,
+    a2: Spanned<ArithmeticExpr>,
+) -> Operands {
+    let a1 = Box::new(folder.fold_aexpr(a1));
+    (a1, Box::new(folder.fold_aexpr(a2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Rename<'a> {
+        from: &'a str,
+        to: &'a str,
+    }
+
+    impl Rename<'_> {
+        fn rename(&self, var: &mut Identifier) {
+            if var == self.from {
+                *var = self.to.to_string();
+            }
+        }
+    }
+
+    impl VisitorMut for Rename<'_> {
+        fn visit_stmt_mut(&mut self, stmt: &mut Spanned<Statement>) {
+            match &mut stmt.node {
+                Statement::Assignment { var, .. }
+                | Statement::CompoundAssignment { var, .. }
+                | Statement::Increment(var)
+                | Statement::Decrement(var)
+                | Statement::For { var, .. } => self.rename(var),
+                _ => (),
+            }
+            walk_stmt_mut(self, stmt)
+        }
+
+        fn visit_aexpr_mut(&mut self, expr: &mut Spanned<ArithmeticExpr>) {
+            match &mut expr.node {
+                ArithmeticExpr::Variable(var)
+                | ArithmeticExpr::PostIncrement(var)
+                | ArithmeticExpr::PostDecrement(var) => self.rename(var),
+                _ => walk_aexpr_mut(self, expr),
+            }
+        }
+    }
+
+    #[test]
+    fn rename_in_place() {
+        let mut program = parse(
+            "x := 1; y := x++; while x < 10 do if y == x then x += 2 else x-- end done; \
+             for i in [x, y + x] do z := i * x done",
+        )
+        .unwrap();
+        Rename { from: "x", to: "w" }.visit_stmt_mut(&mut program);
+
+        let expected = parse(
+            "w := 1; y := w++; while w < 10 do if y == w then w += 2 else w-- end done; \
+             for i in [w, y + w] do z := i * w done",
+        )
+        .unwrap();
+        assert_eq!(program.to_string(), expected.to_string());
+    }
+}
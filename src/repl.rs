@@ -0,0 +1,271 @@
+use crate::cli::*;
+use crate::{pretty_invariant, pretty_states, report_alarms, report_syntax_errors, set_bounds};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use whilep::concrete_semantics::denote::*;
+use whilep::concrete_semantics::state::*;
+use whilep::domain::constant::*;
+use whilep::domain::domain::*;
+use whilep::domain::interval::*;
+use whilep::parser::ast::*;
+use whilep::parser::lower::*;
+use whilep::parser::span::*;
+use whilep::utils::panic::*;
+use whilep::*;
+
+// the name of the typed statements in locations and diagnostics
+const REPL_FILE: &str = "<repl>";
+
+const HELP: &str = "\
+statements are run as soon as they parse, constructs spanning several lines are
+continued until they are complete and an empty line gives up on them
+
+:abs [interval|constant]  show the abstract state after the statements run so far
+:load <file>              run a program against the current state
+:state                    show the current state
+:points                   show the program points of the statements run so far
+:reset                    forget the state and the statements run so far
+:help                     show this message
+:quit                     leave the session";
+
+struct Session {
+    bounds: Option<Bounds>,
+    state: State,
+    // every statement run so far, joined in a single program for the analyses
+    history: String,
+}
+
+pub fn run_repl(bounds: Option<Bounds>) {
+    let mut session = Session {
+        bounds,
+        state: State::new(),
+        history: String::new(),
+    };
+    let mut lines = io::stdin().lock().lines();
+    let mut buffer = String::new();
+
+    info!("whilep {}, :help for help", env!("CARGO_PKG_VERSION"));
+
+    loop {
+        match buffer.is_empty() {
+            true => print!("whilep> "),
+            _ => print!("   ...> "),
+        }
+        io::stdout()
+            .flush()
+            .expect("[ERROR] failed to write the prompt");
+
+        let Some(Ok(line)) = lines.next() else {
+            // end of input, with whatever was left unfinished
+            if !buffer.is_empty() {
+                println!();
+                session.entry(&buffer, true);
+            }
+            println!();
+            return;
+        };
+
+        if buffer.is_empty() {
+            match line.trim() {
+                "" => continue,
+                ":quit" | ":q" => return,
+                command if command.starts_with(':') => {
+                    session.command(command);
+                    continue;
+                }
+                _ => (),
+            }
+        }
+
+        // an empty line gives up on an unfinished construct
+        let give_up = line.trim().is_empty();
+        buffer.push_str(&line);
+        buffer.push('\n');
+
+        if session.entry(&buffer, give_up) {
+            buffer.clear();
+        }
+    }
+}
+
+impl Session {
+    // runs the statements typed so far, returns whether they were consumed or are
+    // still waiting for more lines
+    fn entry(&mut self, text: &str, complete: bool) -> bool {
+        // a trailing `;` is allowed, as statements are typed one at a time
+        let text = text.trim_end();
+        let text = text.strip_suffix(';').unwrap_or(text);
+
+        match parse(text) {
+            Ok(ast) => {
+                if self.run(REPL_FILE, text, &ast) {
+                    println!("{}", pretty_states(std::slice::from_ref(&self.state)));
+                }
+                true
+            }
+            Err(errors) if !complete && errors.iter().any(is_eof) => false,
+            Err(errors) => {
+                report_syntax_errors(REPL_FILE, text, &errors);
+                true
+            }
+        }
+    }
+
+    fn command(&mut self, command: &str) {
+        let (name, arg) = command.split_once(' ').unwrap_or((command, ""));
+        let arg = arg.trim();
+
+        match (name, arg) {
+            (":abs", "" | "interval") => {
+                if let Some(ast) = self.program() {
+                    let (lower, upper) = set_bounds(self.bounds, &ast);
+                    info!("using interval bounds: [{}, {}]", lower, upper);
+                    self.analyze::<Interval>(&ast);
+                }
+            }
+            (":abs", "constant") => {
+                if let Some(ast) = self.program() {
+                    self.analyze::<Constant>(&ast);
+                }
+            }
+            (":abs", domain) => println!("[ERROR] unknown domain `{}`", domain),
+
+            (":load", "") => println!("[ERROR] missing the file to load"),
+            (":load", file) => match fs::read_to_string(file) {
+                Ok(source) => match parse(&source) {
+                    Ok(ast) => {
+                        if self.run(file, &source, &ast) {
+                            info!("loaded {}", file);
+                        }
+                    }
+                    Err(errors) => report_syntax_errors(file, &source, &errors),
+                },
+                Err(e) => println!("[ERROR] failed to read {}: {}", file, e),
+            },
+
+            (":state", _) => {
+                let headers = vec!["#".to_string(), "Var".to_string(), "Val".to_string()];
+                let mut vars = self.state.iter().collect::<Vec<_>>();
+                vars.sort();
+                let rows = vars
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (k, v))| vec![i.to_string(), k.to_string(), v.to_string()])
+                    .collect::<Vec<_>>();
+                draw_table(headers, rows);
+            }
+
+            (":points", _) => {
+                if let Some(ast) = self.program() {
+                    self.points(&ast);
+                }
+            }
+
+            (":reset", _) => {
+                self.state = State::new();
+                self.history.clear();
+                info!("state reset");
+            }
+
+            (":help", _) => println!("{}", HELP),
+
+            _ => println!("[ERROR] unknown command `{}`, :help for help", name),
+        }
+    }
+
+    // runs a program against the current state and keeps it in the history if it
+    // terminates without errors, returns whether it did; a panic of the semantics
+    // leaves the state as it was and the session goes on
+    fn run(&mut self, file: &str, source: &str, ast: &Spanned<Statement>) -> bool {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            denote_stmt(lower(ast.clone()).node)(self.state.clone())
+        }));
+        let result = match result {
+            Ok(result) => result,
+            Err(payload) => {
+                println!("[ERROR] internal error: {}", panic_message(payload));
+                return false;
+            }
+        };
+
+        match result {
+            Ok(Some((state, _))) => {
+                self.state = state;
+                if !self.history.is_empty() {
+                    self.history.push_str(";\n");
+                }
+                self.history.push_str(source.trim_end());
+                true
+            }
+            Ok(None) => {
+                println!("[ERROR] Arithmetic expression error");
+                false
+            }
+            Err(e) => {
+                println!(
+                    "[ERROR] {}",
+                    render_diagnostic(file, source, &e.span, &e.to_string())
+                );
+                false
+            }
+        }
+    }
+
+    // the statements run so far, as a single program
+    fn program(&self) -> Option<Spanned<Statement>> {
+        match parse(&self.history) {
+            Ok(ast) if !self.history.is_empty() => Some(ast),
+            _ => {
+                info!("no statements run yet");
+                None
+            }
+        }
+    }
+
+    fn analyze<T: Domain>(&self, ast: &Spanned<Statement>) {
+        let options = Options::current();
+        let analysis = match panic::catch_unwind(|| analyze::<T>(ast, &options)) {
+            Ok(analysis) => analysis,
+            Err(payload) => {
+                println!("[ERROR] internal error: {}", panic_message(payload));
+                return;
+            }
+        };
+        println!("{}", pretty_invariant(&analysis.exit_state));
+        report_alarms(REPL_FILE, &self.history, &analysis.alarms);
+    }
+
+    fn points(&self, ast: &Spanned<Statement>) {
+        // the history already ran once, so it runs again without errors
+        let Ok(Some(execution)) = evaluate(ast, &Options::current()) else {
+            unreachable!()
+        };
+
+        let headers = vec![
+            "#".to_string(),
+            "Location".to_string(),
+            "Program point".to_string(),
+            "States".to_string(),
+        ];
+        let rows = execution
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                vec![
+                    i.to_string(),
+                    location(REPL_FILE, &p.point.span()),
+                    p.point.to_string(),
+                    pretty_states(&p.states),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        draw_table(headers, rows);
+    }
+}
+
+fn is_eof(error: &Spanned<SyntaxError>) -> bool {
+    matches!(error.node, SyntaxError::UnexpectedEof { .. })
+}
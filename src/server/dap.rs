@@ -0,0 +1,423 @@
+use crate::abstract_semantics::check::*;
+use crate::abstract_semantics::state::State;
+use crate::analysis::*;
+use crate::concrete_semantics::machine::*;
+use crate::concrete_semantics::state::*;
+use crate::domain::constant::*;
+use crate::domain::domain::*;
+use crate::domain::interval::*;
+use crate::parser::ast::*;
+use crate::parser::cfg::*;
+use crate::parser::span::*;
+use crate::server::rpc::*;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+// the only thread of a debuggee, as the language has no concurrency
+const THREAD: i64 = 1;
+// steps run between two checks for a pause request while running
+const STEPS_PER_CHECK: usize = 1000;
+
+// variable references of the scopes of the single stack frame
+const CONCRETE_SCOPE: i64 = 1;
+const INTERVAL_SCOPE: i64 = 2;
+const CONSTANT_SCOPE: i64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Goal {
+    Continue,
+    StepIn,
+    // stop at the next action no deeper than the given depth
+    StepOver(usize),
+    StepOut(usize),
+}
+
+struct Program {
+    path: String,
+    source: String,
+    machine: Machine,
+    cfg: Cfg,
+    interval: Vec<State<Interval>>,
+    constant: Vec<State<Constant>>,
+    stop_on_entry: bool,
+    // the run stopped on a runtime error and cannot go on
+    failed: bool,
+}
+
+struct Session<W: Write> {
+    out: W,
+    seq: i64,
+    program: Option<Program>,
+    breakpoints: Vec<usize>,
+    running: Option<Goal>,
+}
+
+// serves the debug adapter protocol over `input` and `output`, stepping through the
+// program with the small-step machine; messages are read on their own thread so that
+// a `pause` can interrupt a program that is running
+pub fn serve(input: impl BufRead + Send + 'static, output: impl Write) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut input = input;
+        while let Ok(Some(message)) = read_message(&mut input) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut session = Session {
+        out: output,
+        seq: 0,
+        program: None,
+        breakpoints: vec![],
+        running: None,
+    };
+
+    loop {
+        let message = match session.running {
+            Some(goal) => match receiver.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => {
+                    session.run(goal)?;
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => break,
+            },
+            None => match receiver.recv() {
+                Ok(message) => message,
+                Err(_) => break,
+            },
+        };
+
+        if !session.handle(&message)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+impl<W: Write> Session<W> {
+    // handles a request, returns whether the session goes on
+    fn handle(&mut self, request: &Value) -> io::Result<bool> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let args = &request["arguments"];
+
+        match command {
+            "initialize" => {
+                let capabilities = json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsTerminateRequest": true,
+                });
+                self.respond(request, Ok(capabilities))?;
+                self.event("initialized", json!({}))?;
+            }
+
+            "launch" => {
+                let result = self.launch(args);
+                self.respond(request, result)?;
+            }
+
+            "setBreakpoints" => {
+                let lines: Vec<u64> = args["breakpoints"]
+                    .as_array()
+                    .map(|b| b.iter().filter_map(|b| b["line"].as_u64()).collect())
+                    .unwrap_or_default();
+                self.breakpoints = lines.iter().map(|l| *l as usize).collect();
+
+                // only lines where the program can stop hold a breakpoint
+                let breakpoints = lines
+                    .iter()
+                    .map(|line| {
+                        let verified = self.program.as_ref().is_none_or(|p| {
+                            p.cfg
+                                .points
+                                .iter()
+                                .any(|point| point.span().line as u64 == *line)
+                        });
+                        json!({ "verified": verified, "line": line })
+                    })
+                    .collect::<Vec<_>>();
+                self.respond(request, Ok(json!({ "breakpoints": breakpoints })))?;
+            }
+
+            "setExceptionBreakpoints" => self.respond(request, Ok(json!({})))?,
+
+            "configurationDone" => {
+                self.respond(request, Ok(json!({})))?;
+                let Some(program) = &self.program else {
+                    return Ok(true);
+                };
+                match (program.stop_on_entry, program.machine.current()) {
+                    (_, None) => self.finish()?,
+                    (true, _) => self.stopped("entry", None)?,
+                    (_, Some(stop)) if self.breakpoints.contains(&stop.span.line) => {
+                        self.stopped("breakpoint", None)?
+                    }
+                    _ => self.running = Some(Goal::Continue),
+                }
+            }
+
+            "threads" => {
+                let threads = json!({ "threads": [{ "id": THREAD, "name": "main" }] });
+                self.respond(request, Ok(threads))?;
+            }
+
+            "stackTrace" => {
+                let frames = self.frames();
+                let body = json!({ "stackFrames": frames, "totalFrames": frames.len() });
+                self.respond(request, Ok(body))?;
+            }
+
+            "scopes" => {
+                let scopes = json!({ "scopes": [
+                    { "name": "Concrete", "variablesReference": CONCRETE_SCOPE, "expensive": false },
+                    { "name": "Interval", "variablesReference": INTERVAL_SCOPE, "expensive": false },
+                    { "name": "Constant", "variablesReference": CONSTANT_SCOPE, "expensive": false },
+                ]});
+                self.respond(request, Ok(scopes))?;
+            }
+
+            "variables" => {
+                let reference = args["variablesReference"].as_i64().unwrap_or_default();
+                let variables = self.variables(reference);
+                self.respond(request, Ok(json!({ "variables": variables })))?;
+            }
+
+            "continue" | "next" | "stepIn" | "stepOut" => {
+                let depth = self
+                    .program
+                    .as_ref()
+                    .and_then(|p| p.machine.current())
+                    .map_or(0, |stop| stop.depth);
+                let goal = match command {
+                    "continue" => Goal::Continue,
+                    "next" => Goal::StepOver(depth),
+                    "stepIn" => Goal::StepIn,
+                    _ => Goal::StepOut(depth),
+                };
+                self.respond(request, Ok(json!({ "allThreadsContinued": true })))?;
+
+                match &self.program {
+                    Some(p) if !p.failed => self.running = Some(goal),
+                    _ => self.finish()?,
+                }
+            }
+
+            "pause" => {
+                self.respond(request, Ok(json!({})))?;
+                if self.running.is_some() {
+                    self.stopped("pause", None)?;
+                }
+            }
+
+            "disconnect" | "terminate" => {
+                self.respond(request, Ok(json!({})))?;
+                if command == "terminate" {
+                    self.event("terminated", json!({}))?;
+                }
+                return Ok(false);
+            }
+
+            _ => self.respond(request, Err(format!("unsupported request `{}`", command)))?,
+        }
+
+        Ok(true)
+    }
+
+    fn launch(&mut self, args: &Value) -> Result<Value, String> {
+        let path = args["program"]
+            .as_str()
+            .ok_or("missing the `program` to debug")?;
+        let source =
+            fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+
+        let ast = parse(&source).map_err(|errors| {
+            errors
+                .iter()
+                .map(|e| render_diagnostic(path, &source, &e.span, &e.to_string()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+        STRICT_EVALUATION.set(args["strict"].as_bool().unwrap_or(false));
+
+        self.program = Some(Program {
+            path: path.to_string(),
+            machine: Machine::new(&ast),
+            cfg: Cfg::new(&ast),
+            interval: invariants::<Interval>(&ast),
+            constant: invariants::<Constant>(&ast),
+            stop_on_entry: args["stopOnEntry"].as_bool().unwrap_or(false),
+            failed: false,
+            source,
+        });
+        Ok(json!({}))
+    }
+
+    // runs a batch of steps towards `goal`
+    fn run(&mut self, goal: Goal) -> io::Result<()> {
+        for _ in 0..STEPS_PER_CHECK {
+            let Some(program) = &mut self.program else {
+                self.running = None;
+                return Ok(());
+            };
+
+            if let Err(e) = program.machine.step() {
+                let message = e.to_string();
+                let error = render_diagnostic(&program.path, &program.source, &e.span, &message);
+                program.failed = true;
+                self.output("stderr", &format!("{}\n", error))?;
+                return self.stopped("exception", Some(&message));
+            }
+
+            let Some(stop) = program.machine.current() else {
+                return self.finish();
+            };
+
+            let reason = match goal {
+                Goal::StepIn => Some("step"),
+                Goal::StepOver(depth) if stop.depth <= depth => Some("step"),
+                Goal::StepOut(depth) if stop.depth < depth => Some("step"),
+                _ if self.breakpoints.contains(&stop.span.line) => Some("breakpoint"),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                return self.stopped(reason, None);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.running = None;
+        let Some(program) = &self.program else {
+            return Ok(());
+        };
+
+        let exit_code = match program.failed {
+            true => 1,
+            _ => {
+                let state = program.machine.state.pretty();
+                self.output("console", &format!("final state: {}\n", state))?;
+                0
+            }
+        };
+        self.event("exited", json!({ "exitCode": exit_code }))?;
+        self.event("terminated", json!({}))
+    }
+
+    // --- views
+
+    fn frames(&self) -> Vec<Value> {
+        let Some(program) = &self.program else {
+            return vec![];
+        };
+        let Some(stop) = program.machine.current() else {
+            return vec![];
+        };
+
+        let (end_line, end_column) = LineIndex::new(&program.source).position(stop.span.end);
+        vec![json!({
+            "id": 0,
+            "name": program.cfg.points[stop.point].to_string(),
+            "source": { "path": program.path },
+            "line": stop.span.line,
+            "column": stop.span.column,
+            "endLine": end_line,
+            "endColumn": end_column,
+        })]
+    }
+
+    fn variables(&self, reference: i64) -> Vec<Value> {
+        let Some(program) = &self.program else {
+            return vec![];
+        };
+        let Some(stop) = program.machine.current() else {
+            return vec![];
+        };
+
+        let mut variables = match reference {
+            CONCRETE_SCOPE => program
+                .machine
+                .state
+                .iter()
+                .map(|(var, val)| (var.clone(), val.to_string()))
+                .collect(),
+            INTERVAL_SCOPE => abstract_variables(&program.cfg, &program.interval, stop.point),
+            CONSTANT_SCOPE => abstract_variables(&program.cfg, &program.constant, stop.point),
+            _ => vec![],
+        };
+        variables.sort();
+
+        variables
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": value, "variablesReference": 0 }))
+            .collect()
+    }
+
+    // --- messages
+
+    fn respond(&mut self, request: &Value, result: Result<Value, String>) -> io::Result<()> {
+        self.seq += 1;
+        let mut response = json!({
+            "seq": self.seq,
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        write_message(&mut self.out, &response)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.seq += 1;
+        let message = json!({ "seq": self.seq, "type": "event", "event": event, "body": body });
+        write_message(&mut self.out, &message)
+    }
+
+    fn stopped(&mut self, reason: &str, text: Option<&str>) -> io::Result<()> {
+        self.running = None;
+        let mut body = json!({ "reason": reason, "threadId": THREAD, "allThreadsStopped": true });
+        if let Some(text) = text {
+            body["text"] = json!(text);
+        }
+        self.event("stopped", body)
+    }
+
+    fn output(&mut self, category: &str, output: &str) -> io::Result<()> {
+        self.event("output", json!({ "category": category, "output": output }))
+    }
+}
+
+fn invariants<T: Domain>(ast: &Spanned<Statement>) -> Vec<State<T>> {
+    analyze::<T>(ast, &Options::current())
+        .points
+        .into_iter()
+        .map(|p| p.invariant)
+        .collect()
+}
+
+// the abstract state before the point the machine is paused at, as the concrete
+// state shown next to it has not run the paused action yet
+fn abstract_variables<T: Domain>(
+    cfg: &Cfg,
+    invariants: &[State<T>],
+    point: usize,
+) -> Vec<(String, String)> {
+    match state_before(cfg, invariants, point) {
+        State::Bottom => vec![("(unreachable)".to_string(), "bottom".to_string())],
+        State::Just(vars) => vars
+            .iter()
+            .map(|(var, val)| (var.clone(), val.to_string()))
+            .collect(),
+    }
+}
@@ -0,0 +1,426 @@
+use crate::analysis::*;
+use crate::concrete_semantics::machine::*;
+use crate::domain::constant::*;
+use crate::domain::domain::*;
+use crate::domain::interval::*;
+use crate::output::json;
+use crate::parser::ast::*;
+use crate::parser::format::*;
+use crate::parser::span::*;
+use crate::types::integer::*;
+use crate::utils::panic::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// the name of the submitted program in locations
+const REQUEST_FILE: &str = "<request>";
+// programs larger than this are refused
+const MAX_BODY: usize = 1 << 20;
+// steps run between two checks of the clock while evaluating
+const STEPS_PER_CHECK: usize = 1024;
+
+// the most a single request may use, requests can only ask for less
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub steps: usize,
+    pub timeout: Duration,
+}
+
+// what every request starts from, the query of a request overriding some of it
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub limits: Limits,
+    // the options of the command line and of whilep.toml, carried to the threads
+    // of the analyses
+    pub options: Options,
+    // reseeds the values drawn from `[a, b]` for each run, so that the same
+    // request always gets the same answer
+    pub seed: Option<u64>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    body: String,
+}
+
+type Response = (u16, Value);
+
+// serves the json api on `listener`, one request at a time
+pub fn serve(listener: &TcpListener, config: &Config) -> io::Result<()> {
+    for stream in listener.incoming() {
+        // a client that hangs up or sends garbage only loses its own connection
+        let _ = connection(stream?, config);
+    }
+    Ok(())
+}
+
+fn connection(stream: TcpStream, config: &Config) -> io::Result<()> {
+    stream.set_read_timeout(Some(config.limits.timeout))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let (status, body) = match read_request(&mut reader) {
+        Ok(request) => route(&request, config),
+        Err(response) => response,
+    };
+
+    let body = body.to_string();
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn route(request: &Request, config: &Config) -> Response {
+    let endpoint: fn(&Request, &Config) -> Response = match request.path.as_str() {
+        "/parse" => parse_endpoint,
+        "/eval" => eval_endpoint,
+        "/analyze" => analyze_endpoint,
+        "/format" => format_endpoint,
+        path => return error(404, &format!("unknown endpoint `{}`", path)),
+    };
+
+    match request.method.as_str() {
+        // a bug in the semantics only fails the request that ran into it
+        "POST" => match panic::catch_unwind(|| endpoint(request, config)) {
+            Ok(response) => response,
+            Err(payload) => error(500, &format!("internal error: {}", panic_message(payload))),
+        },
+        method => error(405, &format!("`{}` is not allowed, use POST", method)),
+    }
+}
+
+// --- endpoints
+
+fn parse_endpoint(request: &Request, _: &Config) -> Response {
+    match parse(&request.body) {
+        Ok(ast) => ok(json!({ "program": json::program(&ast) })),
+        Err(errors) => syntax_errors(&errors),
+    }
+}
+
+// runs the program with the small-step machine, so that it can be stopped after
+// a number of steps or some time, returning the state reached in that case
+fn eval_endpoint(request: &Request, config: &Config) -> Response {
+    let limits = config.limits;
+    let ast = match parse(&request.body) {
+        Ok(ast) => ast,
+        Err(errors) => return syntax_errors(&errors),
+    };
+    let (steps, timeout, strict) = match (
+        limit(request, "steps", limits.steps),
+        limit(request, "timeout", limits.timeout.as_millis() as usize),
+        flag(request, "strict", config.options.strict),
+    ) {
+        (Ok(steps), Ok(timeout), Ok(strict)) => (steps, timeout, strict),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return error(400, &e),
+    };
+    let timeout = Duration::from_millis(timeout as u64);
+
+    let options = Options {
+        strict,
+        ..config.options.clone()
+    };
+    let _applied = options.apply();
+    if let Some(seed) = config.seed {
+        seed_random(seed);
+    }
+
+    let start = Instant::now();
+    let mut machine = Machine::new(&ast);
+    let mut count = 0;
+
+    while machine.current().is_some() {
+        let reached = if count >= steps {
+            Some("steps")
+        } else if count % STEPS_PER_CHECK == 0 && start.elapsed() > timeout {
+            Some("timeout")
+        } else {
+            None
+        };
+        if let Some(limit) = reached {
+            return ok(json!({
+                "limit": limit,
+                "steps": count,
+                "state": json::concrete_state(&machine.state),
+            }));
+        }
+
+        if let Err(e) = machine.step() {
+            return ok(json!({
+                "steps": count,
+                "error": json::diagnostic(REQUEST_FILE, &e.span, &e.to_string()),
+            }));
+        }
+        count += 1;
+    }
+
+    ok(json!({
+        "steps": count,
+        "final_state": json::concrete_state(&machine.state),
+    }))
+}
+
+fn analyze_endpoint(request: &Request, config: &Config) -> Response {
+    let limits = config.limits;
+    let ast = match parse(&request.body) {
+        Ok(ast) => ast,
+        Err(errors) => return syntax_errors(&errors),
+    };
+    let (timeout, strict) = match (
+        limit(request, "timeout", limits.timeout.as_millis() as usize),
+        flag(request, "strict", config.options.strict),
+    ) {
+        (Ok(timeout), Ok(strict)) => (Duration::from_millis(timeout as u64), strict),
+        (Err(e), _) | (_, Err(e)) => return error(400, &e),
+    };
+    let options = Options {
+        strict,
+        ..config.options.clone()
+    };
+
+    let domain = request
+        .query
+        .get("domain")
+        .map_or("interval", |d| d.as_str());
+    let mut response = match domain {
+        "interval" => {
+            let bounds = request
+                .query
+                .get("bounds")
+                .map_or("[neginf, posinf]", |b| b.as_str());
+            let (lower, upper) = match interval_bounds(bounds, &ast) {
+                Ok(bounds) => bounds,
+                Err(e) => return error(400, &e),
+            };
+            let options = Options {
+                bounds: (lower, upper),
+                ..options
+            };
+            let mut response = run_analysis::<Interval>(ast, options, timeout);
+            response.1["bounds"] = json!([lower, upper]);
+            response
+        }
+        "constant" => run_analysis::<Constant>(ast, options, timeout),
+        domain => return error(400, &format!("unknown domain `{}`", domain)),
+    };
+
+    response.1["domain"] = json!(domain);
+    response
+}
+
+fn format_endpoint(request: &Request, _: &Config) -> Response {
+    match format_source(&request.body) {
+        Ok(formatted) => ok(json!({
+            "changed": formatted != request.body,
+            "formatted": formatted,
+        })),
+        Err(errors) => syntax_errors(&errors),
+    }
+}
+
+// the analysis runs on its own thread to give up on it after `timeout`, when it is
+// cancelled and stops at the next iteration of a loop
+fn run_analysis<T: Domain + Send + 'static>(
+    ast: Spanned<Statement>,
+    options: Options,
+    timeout: Duration,
+) -> Response {
+    let (sender, receiver) = mpsc::channel();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let flag = cancel.clone();
+    thread::spawn(move || {
+        if let Some(analysis) = try_analyze::<T>(&ast, &options, &flag) {
+            let _ = sender.send(json::analysis(REQUEST_FILE, &analysis));
+        }
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(analysis) => ok(json!({ "analysis": analysis })),
+        Err(RecvTimeoutError::Timeout) => {
+            cancel.store(true, Ordering::Relaxed);
+            ok(json!({ "limit": "timeout" }))
+        }
+        Err(RecvTimeoutError::Disconnected) => error(500, "the analysis failed"),
+    }
+}
+
+// --- requests
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let bad_request = |_| error(400, "malformed request");
+
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(bad_request)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(error(400, "malformed request line"));
+    };
+
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(bad_request)? == 0 {
+            break;
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| error(400, "invalid Content-Length header"))?;
+            }
+        }
+    }
+
+    if length > MAX_BODY {
+        return Err(error(
+            413,
+            &format!("programs are limited to {} bytes", MAX_BODY),
+        ));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(bad_request)?;
+    let body = String::from_utf8(body).map_err(|_| error(400, "the body is not utf-8"))?;
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (name, value) = p.split_once('=').unwrap_or((p, ""));
+            (decode(name), decode(value))
+        })
+        .collect();
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+        body,
+    })
+}
+
+// a limit asked for in the query, never above the one of the server
+fn limit(request: &Request, name: &str, max: usize) -> Result<usize, String> {
+    match request.query.get(name) {
+        None => Ok(max),
+        Some(value) => match value.parse::<usize>() {
+            Ok(value) => Ok(value.min(max)),
+            Err(_) => Err(format!("`{}` must be a non-negative integer", name)),
+        },
+    }
+}
+
+// a flag of the query, `default` when absent
+fn flag(request: &Request, name: &str, default: bool) -> Result<bool, String> {
+    match request.query.get(name).map(|v| v.as_str()) {
+        None => Ok(default),
+        Some("false") => Ok(false),
+        Some("" | "true") => Ok(true),
+        Some(_) => Err(format!("`{}` must be true or false", name)),
+    }
+}
+
+// the bounds of the interval domain, as `auto` or `[a, b]` like `--bounds`
+fn interval_bounds(bounds: &str, ast: &Spanned<Statement>) -> Result<(Integer, Integer), String> {
+    if bounds == "auto" {
+        let max = ast
+            .get_max_number()
+            .map(Integer::Value)
+            .unwrap_or(Integer::PosInf);
+        return Ok((-max, max));
+    }
+
+    match Interval::from_str(bounds) {
+        Ok(Interval::Range(min, max)) if min <= max => Ok((min, max)),
+        _ => Err(format!("invalid bounds `{}`", bounds)),
+    }
+}
+
+// percent-decoding of query components, where `+` is a space
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = text
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// --- responses
+
+fn ok(mut body: Value) -> Response {
+    body["version"] = json!(json::SCHEMA_VERSION);
+    (200, body)
+}
+
+fn error(status: u16, message: &str) -> Response {
+    (
+        status,
+        json!({ "version": json::SCHEMA_VERSION, "message": message }),
+    )
+}
+
+fn syntax_errors(errors: &[Spanned<SyntaxError>]) -> Response {
+    let body = json!({
+        "version": json::SCHEMA_VERSION,
+        "message": format!("found {} syntax error(s)", errors.len()),
+        "errors": json::syntax_errors(REQUEST_FILE, errors),
+    });
+    (422, body)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    }
+}
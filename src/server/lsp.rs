@@ -0,0 +1,374 @@
+use crate::abstract_semantics::check::*;
+use crate::abstract_semantics::state::*;
+use crate::analysis::*;
+use crate::domain::constant::*;
+use crate::domain::interval::*;
+use crate::domain::lattice::*;
+use crate::parser::ast::*;
+use crate::parser::format::*;
+use crate::parser::lexer::*;
+use crate::server::rpc::*;
+use crate::utils::panic::*;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+// json-rpc error codes
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INTERNAL_ERROR: i64 = -32603;
+
+type Response = Result<Value, (i64, String)>;
+
+struct Server<W: Write> {
+    out: W,
+    // the text of each open document, by uri
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+// serves the language server protocol over `input` and `output` until the client
+// asks to exit, returns whether it asked for a shutdown first
+pub fn serve(input: impl BufRead, output: impl Write) -> io::Result<bool> {
+    let mut input = input;
+    let mut server = Server {
+        out: output,
+        documents: HashMap::new(),
+        shutdown: false,
+    };
+
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        if method == "exit" {
+            break;
+        }
+
+        let response = match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                server.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                server.open(uri, text)?;
+                continue;
+            }
+            "textDocument/didChange" => {
+                // the server asks for full syncs, the last change is the whole text
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()).map(|c| &c["text"]) {
+                    server.open(uri, text.as_str().unwrap_or_default())?;
+                }
+                continue;
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                server.documents.remove(uri);
+                server.notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )?;
+                continue;
+            }
+            "textDocument/hover" => server.with_document(params, hover),
+            "textDocument/formatting" => server.with_document(params, formatting),
+            "textDocument/definition" => server.with_document(params, definition),
+            "textDocument/rename" => server.with_document(params, rename),
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method `{}`", method))),
+        };
+
+        // notifications have no id and get no response, even when not supported
+        if let Some(id) = message.get("id") {
+            let message = match response {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err((code, error)) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": code, "message": error },
+                }),
+            };
+            write_message(&mut server.out, &message)?;
+        }
+    }
+
+    Ok(server.shutdown)
+}
+
+impl<W: Write> Server<W> {
+    fn open(&mut self, uri: &str, text: &str) -> io::Result<()> {
+        self.documents.insert(uri.to_string(), text.to_string());
+        self.notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics(text) }),
+        )
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        write_message(&mut self.out, &message)
+    }
+
+    fn with_document(
+        &self,
+        params: &Value,
+        handler: fn(&str, &str, &Value) -> Response,
+    ) -> Response {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match self.documents.get(uri) {
+            Some(text) => handler(uri, text, params),
+            None => Err((INVALID_PARAMS, format!("unknown document `{}`", uri))),
+        }
+    }
+}
+
+fn capabilities() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": 1,
+            "hoverProvider": true,
+            "documentFormattingProvider": true,
+            "definitionProvider": true,
+            "renameProvider": true,
+        },
+        "serverInfo": { "name": "whilep", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+// --- features
+
+// the syntax errors or, for a program that parses, the alarms of both domains;
+// an analysis that panics gives a single error over the whole program instead
+fn diagnostics(text: &str) -> Vec<Value> {
+    let alarms = match parse(text) {
+        Ok(ast) => {
+            let options = Options::current();
+            let analyses = panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut alarms = analyze::<Interval>(&ast, &options).alarms;
+                alarms.extend(analyze::<Constant>(&ast, &options).alarms);
+                alarms
+            }));
+            match analyses {
+                Ok(alarms) => dedup(alarms),
+                Err(payload) => vec![Alarm {
+                    rule: "internal-error",
+                    severity: Severity::Error,
+                    message: format!("the analysis failed: {}", panic_message(payload)),
+                    span: ast.span,
+                }],
+            }
+        }
+        Err(errors) => errors
+            .iter()
+            .map(|e| Alarm {
+                rule: "syntax-error",
+                severity: Severity::Error,
+                message: e.to_string(),
+                span: e.span,
+            })
+            .collect(),
+    };
+
+    alarms
+        .iter()
+        .map(|alarm| {
+            json!({
+                "range": range(text, alarm.span.start, alarm.span.end),
+                "severity": match alarm.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                },
+                "code": alarm.rule,
+                "source": "whilep",
+                "message": alarm.message,
+            })
+        })
+        .collect()
+}
+
+// the invariants of the innermost program point under the cursor; the points of a
+// repeat body appear twice and are shown joined
+fn hover(_: &str, text: &str, params: &Value) -> Response {
+    let offset = offset(text, &params["position"]);
+    let Some(ast) = parse_partial(text).0 else {
+        return Ok(Value::Null);
+    };
+
+    let options = Options::current();
+    let analyses = panic::catch_unwind(AssertUnwindSafe(|| {
+        (
+            analyze::<Interval>(&ast, &options),
+            analyze::<Constant>(&ast, &options),
+        )
+    }));
+    let (interval, constant) = analyses.map_err(|payload| {
+        let message = format!("the analysis failed: {}", panic_message(payload));
+        (INTERNAL_ERROR, message)
+    })?;
+
+    let Some(span) = interval
+        .points
+        .iter()
+        .map(|p| p.point.span())
+        .filter(|s| s.start <= offset && offset <= s.end)
+        .min_by_key(|s| s.end - s.start)
+    else {
+        return Ok(Value::Null);
+    };
+
+    // a guard shares its span with the exit of its loop or the other branch of its if
+    let indices = (0..interval.points.len())
+        .filter(|i| interval.points[*i].point.span() == span)
+        .collect::<Vec<_>>();
+    let mut kinds: Vec<&str> = vec![];
+    for i in &indices {
+        let kind = interval.points[*i].point.kind();
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+
+    let contents = kinds
+        .iter()
+        .map(|kind| {
+            let group = indices
+                .iter()
+                .filter(|i| interval.points[**i].point.kind() == *kind)
+                .collect::<Vec<_>>();
+            let interval_state = group.iter().fold(State::Bottom, |acc, i| {
+                acc.lub(&interval.points[**i].invariant)
+            });
+            let constant_state = group.iter().fold(State::Bottom, |acc, i| {
+                acc.lub(&constant.points[**i].invariant)
+            });
+            format!(
+                "```\n{}\n```\nInterval: `{}`  \nConstant: `{}`",
+                interval.points[*group[0]].point, interval_state, constant_state
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n");
+
+    Ok(json!({
+        "contents": { "kind": "markdown", "value": contents },
+        "range": range(text, span.start, span.end),
+    }))
+}
+
+fn formatting(_: &str, text: &str, _: &Value) -> Response {
+    match format_source(text) {
+        Ok(formatted) if formatted != text => Ok(json!([{
+            "range": range(text, 0, text.len()),
+            "newText": formatted,
+        }])),
+        // nothing to do, or nothing that can be done until the program parses
+        _ => Ok(json!([])),
+    }
+}
+
+// the first place where the variable under the cursor is assigned
+fn definition(uri: &str, text: &str, params: &Value) -> Response {
+    let offset = offset(text, &params["position"]);
+    let Some(var) = variable_at(text, offset) else {
+        return Ok(Value::Null);
+    };
+
+    let tokens = Lexer::new(text).flatten().collect::<Vec<_>>();
+    let assignment = tokens.iter().enumerate().find(|(i, (_, token, _))| {
+        let assigned = matches!(
+            tokens.get(i + 1).map(|t| &t.1),
+            Some(
+                Token::Assign
+                    | Token::AddAssign
+                    | Token::SubAssign
+                    | Token::MulAssign
+                    | Token::DivAssign
+                    | Token::Increment
+                    | Token::Decrement
+            )
+        );
+        let loop_var = *i > 0 && tokens[i - 1].1 == Token::For;
+        *token == Token::Identifier(var.clone()) && (assigned || loop_var)
+    });
+
+    Ok(match assignment {
+        Some((_, (start, _, end))) => json!({ "uri": uri, "range": range(text, *start, *end) }),
+        None => Value::Null,
+    })
+}
+
+fn rename(uri: &str, text: &str, params: &Value) -> Response {
+    let offset = offset(text, &params["position"]);
+    let new_name = params["newName"].as_str().unwrap_or_default();
+
+    let tokens = Lexer::new(new_name).collect::<Vec<_>>();
+    if !matches!(&tokens[..], [Ok((0, Token::Identifier(_), end))] if *end == new_name.len()) {
+        return Err((
+            INVALID_PARAMS,
+            format!("`{}` is not a variable name", new_name),
+        ));
+    }
+
+    let Some(var) = variable_at(text, offset) else {
+        return Err((INVALID_PARAMS, "no variable under the cursor".to_string()));
+    };
+
+    let edits = Lexer::new(text)
+        .flatten()
+        .filter(|(_, token, _)| *token == Token::Identifier(var.clone()))
+        .map(|(start, _, end)| json!({ "range": range(text, start, end), "newText": new_name }))
+        .collect::<Vec<_>>();
+
+    Ok(json!({ "changes": { uri: edits } }))
+}
+
+// --- helpers
+
+fn variable_at(text: &str, offset: usize) -> Option<Identifier> {
+    Lexer::new(text)
+        .flatten()
+        .find(|(start, _, end)| *start <= offset && offset <= *end)
+        .and_then(|(_, token, _)| match token {
+            Token::Identifier(var) => Some(var),
+            _ => None,
+        })
+}
+
+// lsp positions count lines from 0 and characters in utf-16 code units
+fn position(text: &str, offset: usize) -> Value {
+    let line = text[..offset].matches('\n').count();
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let character = text[line_start..offset].encode_utf16().count();
+    json!({ "line": line, "character": character })
+}
+
+fn range(text: &str, start: usize, end: usize) -> Value {
+    json!({ "start": position(text, start), "end": position(text, end) })
+}
+
+fn offset(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or_default() as usize;
+    let character = position["character"].as_u64().unwrap_or_default() as usize;
+
+    let line_start = match line {
+        0 => 0,
+        _ => text
+            .match_indices('\n')
+            .nth(line - 1)
+            .map_or(text.len(), |(i, _)| i + 1),
+    };
+
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
@@ -0,0 +1,4 @@
+pub mod dap;
+pub mod http;
+pub mod lsp;
+pub mod rpc;
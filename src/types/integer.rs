@@ -1,5 +1,8 @@
 use crate::types::sign::*;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
 use std::{
     fmt,
     ops::{self},
@@ -19,9 +22,35 @@ pub enum Integer {
 impl Integer {
     pub fn value(&self) -> i64 {
         match *self {
-            Integer::NegInf => std::i64::MIN,
+            Integer::NegInf => i64::MIN,
             Integer::Value(v) => v,
-            Integer::PosInf => std::i64::MAX,
+            Integer::PosInf => i64::MAX,
+        }
+    }
+
+    // the arithmetic of the concrete semantics, `None` when an operand is infinite
+    // or the result is out of the range of i64
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.checked(other, i64::checked_add)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked(other, i64::checked_sub)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        self.checked(other, i64::checked_mul)
+    }
+
+    // also `None` for a zero divisor
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        self.checked(other, i64::checked_div)
+    }
+
+    fn checked(self, other: Self, op: fn(i64, i64) -> Option<i64>) -> Option<Self> {
+        match (self, other) {
+            (Integer::Value(a), Integer::Value(b)) => op(a, b).map(Integer::Value),
+            _ => None,
         }
     }
 }
@@ -36,6 +65,16 @@ impl fmt::Display for Integer {
     }
 }
 
+// finite values as JSON numbers, infinities by name
+impl Serialize for Integer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Integer::Value(v) => serializer.serialize_i64(*v),
+            _ => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
 impl FromStr for Integer {
     type Err = std::num::ParseIntError;
 
@@ -62,7 +101,7 @@ impl ops::Neg for Integer {
         match self {
             Integer::NegInf => Integer::PosInf,
             Integer::PosInf => Integer::NegInf,
-            Integer::Value(v) => Integer::Value(-v),
+            Integer::Value(v) => v.checked_neg().map_or(Integer::PosInf, Integer::Value),
         }
     }
 }
@@ -91,7 +130,10 @@ impl ops::Sub<Integer> for Integer {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
-        self + (-other)
+        match (self, other) {
+            (Integer::Value(_), Integer::Value(b)) => self - b,
+            _ => self + (-other),
+        }
     }
 }
 
@@ -118,7 +160,7 @@ impl ops::Div<Integer> for Integer {
             (Integer::NegInf, b) if b > ZERO => Integer::NegInf,
             (Integer::NegInf, b) if b < ZERO => Integer::PosInf,
             (Integer::PosInf, b) if b < ZERO => Integer::NegInf,
-            (Integer::PosInf, b) if b > ZERO => Integer::NegInf,
+            (Integer::PosInf, b) if b > ZERO => Integer::PosInf,
             (Integer::Value(a), b) if a > 0 && b == ZERO => Integer::PosInf,
             (Integer::Value(a), b) if a < 0 && b == ZERO => Integer::NegInf,
             (Integer::Value(a), Integer::Value(b)) => Integer::Value(a / b),
@@ -136,7 +178,8 @@ impl ops::Add<i64> for Integer {
             Integer::NegInf => Integer::NegInf,
             Integer::Value(n) => match n.checked_add(i) {
                 Some(val) => Integer::Value(val),
-                None => Integer::PosInf,
+                None if i > 0 => Integer::PosInf,
+                None => Integer::NegInf,
             },
         }
     }
@@ -145,8 +188,16 @@ impl ops::Add<i64> for Integer {
 impl ops::Sub<i64> for Integer {
     type Output = Self;
 
-    fn sub(self, other: i64) -> Self {
-        self + -other
+    fn sub(self, i: i64) -> Self {
+        match self {
+            Integer::PosInf => Integer::PosInf,
+            Integer::NegInf => Integer::NegInf,
+            Integer::Value(n) => match n.checked_sub(i) {
+                Some(val) => Integer::Value(val),
+                None if i < 0 => Integer::PosInf,
+                None => Integer::NegInf,
+            },
+        }
     }
 }
 
@@ -171,10 +222,23 @@ impl ops::Mul<i64> for Integer {
     }
 }
 
+thread_local! {
+    // the generator of the values of `[a, b]`, seeded for reproducible runs
+    static RNG: RefCell<Option<ChaCha8Rng>> = const { RefCell::new(None) };
+}
+
+pub fn seed_random(seed: u64) {
+    RNG.set(Some(ChaCha8Rng::seed_from_u64(seed)));
+}
+
 pub fn random_integer_between(min: Integer, max: Integer) -> Integer {
     if min > max {
         panic!("[ERROR] invalid interval: [{}, {}]", min, max);
     }
 
-    Integer::Value(rand::thread_rng().gen_range(min.value()..=max.value()))
+    let range = min.value()..=max.value();
+    Integer::Value(RNG.with_borrow_mut(|rng| match rng {
+        Some(rng) => rng.gen_range(range),
+        None => rand::thread_rng().gen_range(range),
+    }))
 }
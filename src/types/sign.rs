@@ -10,7 +10,7 @@ pub fn sign(i: Integer) -> Sign {
     match i {
         Integer::NegInf => Sign::Neg,
         Integer::Value(n) if n < 0 => Sign::Neg,
-        Integer::Value(n) if n == 0 => Sign::Zero,
+        Integer::Value(0) => Sign::Zero,
         Integer::Value(n) if n > 0 => Sign::Pos,
         Integer::PosInf => Sign::Pos,
         _ => unreachable!(),
@@ -0,0 +1,13 @@
+use std::any::Any;
+
+// the message a panic was raised with, when it has one
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match (
+        payload.downcast_ref::<&str>(),
+        payload.downcast_ref::<String>(),
+    ) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "unknown panic".to_string(),
+    }
+}
@@ -0,0 +1,62 @@
+// compares the analyses with the concrete runs of random programs, the ones of a
+// fixed range of seeds so that every run checks the same programs; `whilep fuzz`
+// tries as many others as wanted
+
+use whilep::analysis::Options;
+use whilep::domain::constant::*;
+use whilep::domain::interval::*;
+use whilep::fuzz::differential::*;
+use whilep::fuzz::generator::*;
+use whilep::types::integer::*;
+
+const SEEDS: u64 = 200;
+// seeds out of the range that found a bug, checked as well: their concrete runs
+// overflowed and then panicked adding the infinities they saturated to
+const REGRESSIONS: &[u64] = &[1749, 2152, 2283, 2370];
+
+#[test]
+fn differential() {
+    let options = GeneratorOptions::default();
+    let mut failures = vec![];
+
+    for seed in (0..SEEDS).chain(REGRESSIONS.iter().copied()) {
+        let (source, findings) = fuzz(&options, seed);
+        if !findings.is_empty() {
+            let findings = findings.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+            failures.push(format!(
+                "seed {}:\n{}\n{}",
+                seed,
+                findings.join("\n"),
+                source
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+// the program of `seed` and what comparing its semantics found
+fn fuzz(options: &GeneratorOptions, seed: u64) -> (String, Vec<Finding>) {
+    let (source, program) = match print_program(&generate(options, seed)) {
+        Ok(program) => program,
+        Err(finding) => return (String::new(), vec![finding]),
+    };
+    let outcome = match run_concrete(&program, seed, &Options::default()) {
+        Ok(outcome) => outcome,
+        Err(finding) => return (source, vec![finding]),
+    };
+
+    let mut findings = vec![];
+    // unbounded, then with bounds narrower than most of the values
+    for bounds in [(Integer::NegInf, Integer::PosInf), (-ONE * 5, ONE * 5)] {
+        let options = Options {
+            bounds,
+            ..Options::default()
+        };
+        let found = compare::<Interval>("interval", &program, &outcome, &options);
+        findings.extend(found);
+    }
+    let found = compare::<Constant>("constant", &program, &outcome, &Options::default());
+    findings.extend(found);
+    (source, findings)
+}
@@ -0,0 +1,306 @@
+// runs every program of tests/golden and compares what whilep finds about it with
+// the `.out` file next to it; `BLESS=1 cargo test --test golden` rewrites the
+// `.out` files instead, to be reviewed with the change that moved them
+//
+// a program can start with `// bounds: [a, b]` to set the bounds of the interval
+// domain, `// no-run` to skip the concrete semantics when it does not terminate, or
+// `// strict` to evaluate both operands of `&&` and `||`
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use whilep::concrete_semantics::state::*;
+use whilep::domain::constant::*;
+use whilep::domain::domain::*;
+use whilep::domain::interval::*;
+use whilep::parser::ast::*;
+use whilep::parser::format::*;
+use whilep::parser::span::*;
+use whilep::types::integer::*;
+use whilep::*;
+
+const CORPUS: &str = "tests/golden";
+
+#[test]
+fn golden() {
+    let bless = env::var("BLESS").is_ok_and(|v| v != "0");
+    let mut failures = vec![];
+
+    for program in corpus() {
+        let expected_file = program.with_extension("out");
+        let actual = render(&program);
+
+        if bless {
+            fs::write(&expected_file, &actual).unwrap();
+            continue;
+        }
+
+        match fs::read_to_string(&expected_file) {
+            Ok(expected) if expected == actual => (),
+            Ok(expected) => failures.push(mismatch(&program, &expected, &actual)),
+            Err(_) => failures.push(format!(
+                "{}: no {}, run with BLESS=1 to write it",
+                program.display(),
+                expected_file.display()
+            )),
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+// formatting a program of the corpus gives a source that parses back to the same
+// program, and that formatting again leaves as it is
+#[test]
+fn format_round_trip() {
+    let mut failures = vec![];
+
+    for program in corpus() {
+        let source = fs::read_to_string(&program).unwrap();
+        let Ok(ast) = parse(&source) else {
+            continue;
+        };
+
+        let formatted = match format_source(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                failures.push(format!("{}: {}", program.display(), errors[0]));
+                continue;
+            }
+        };
+        match parse(&formatted) {
+            Ok(reparsed) if reparsed.to_string() != ast.to_string() => failures.push(format!(
+                "{}: the formatted program differs\n{}",
+                program.display(),
+                formatted
+            )),
+            Ok(_) => (),
+            Err(errors) => failures.push(format!(
+                "{}: the formatted program does not parse: {}\n{}",
+                program.display(),
+                errors[0],
+                formatted
+            )),
+        }
+        if format_source(&formatted).ok().as_ref() != Some(&formatted) {
+            failures.push(format!("{}: formatting twice differs", program.display()));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+// literals keep the radix and separators they are written with, and comments
+// inside an expression stay where they are
+#[test]
+fn format_keeps_literals() {
+    let source = "\
+x := 0x10 + 1_000; // c
+y := 0b101 * -0x2A - /* inner */ x;
+z := -(5) + -9223372036854775808 + (0 - x) * 2;
+w := [neginf, 0x7F] + 1 + // why
+    2
+";
+    assert_eq!(format_source(source).unwrap(), source);
+}
+
+// the literal of the least value is only in range with its sign
+#[test]
+fn least_value_prints_back() {
+    let least = ArithmeticExpr::Number(Integer::Value(i64::MIN));
+    let source = format!("x := {}", least);
+    assert_eq!(source, "x := -9223372036854775808");
+    let ast = parse(&source).unwrap();
+
+    seed_random(0);
+    let execution = evaluate(&ast, &Options::default()).unwrap().unwrap();
+    assert_eq!(execution.final_state["x"], Integer::Value(i64::MIN));
+}
+
+// outputs with the same lines still differ by what ends them
+#[test]
+fn mismatch_of_line_endings() {
+    let program = Path::new("program.wp");
+    assert!(mismatch(program, "x\n", "x").contains("trailing newline differs"));
+    assert!(mismatch(program, "x\r\ny\r\n", "x\ny\n").contains("line endings differ"));
+    assert!(mismatch(program, "x\n", "y\n").contains("differs at line 1"));
+}
+
+fn corpus() -> Vec<PathBuf> {
+    let mut programs = fs::read_dir(CORPUS)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "wp"))
+        .collect::<Vec<_>>();
+    programs.sort();
+    assert!(!programs.is_empty(), "no program in {}", CORPUS);
+    programs
+}
+
+// the first line that differs, with the command to see the whole difference
+fn mismatch(program: &Path, expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            // the lines are the same, the difference is in what ends them
+            (None, None) => {
+                return format!(
+                    "{}: {} in the output\nrun with BLESS=1 and review the diff to accept the change",
+                    program.display(),
+                    byte_difference(expected, actual),
+                )
+            }
+            (e, a) if e == a => line += 1,
+            (e, a) => {
+                return format!(
+                    "{}: differs at line {} of the output\n  expected: {}\n  actual:   {}\nrun with BLESS=1 and review the diff to accept the change",
+                    program.display(),
+                    line,
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>"),
+                )
+            }
+        }
+    }
+}
+
+// what differs between two outputs with the same lines
+fn byte_difference(expected: &str, actual: &str) -> String {
+    let unix = |s: &str| s.replace("\r\n", "\n");
+    let ending = |s: &str| match s.contains("\r\n") {
+        true => "\\r\\n",
+        _ => "\\n",
+    };
+    if unix(expected) == unix(actual) {
+        return format!(
+            "line endings differ, expected {} and actual {}",
+            ending(expected),
+            ending(actual)
+        );
+    }
+    if expected.trim_end_matches(['\r', '\n']) == actual.trim_end_matches(['\r', '\n']) {
+        return "trailing newline differs".to_string();
+    }
+    let offset = expected
+        .bytes()
+        .zip(actual.bytes())
+        .position(|(e, a)| e != a)
+        .unwrap_or(expected.len().min(actual.len()));
+    format!("differs at byte {}", offset)
+}
+
+// --- rendering
+
+fn render(program: &Path) -> String {
+    let file = program.file_name().unwrap().to_string_lossy().to_string();
+    let source = fs::read_to_string(program).unwrap();
+    let mut out = String::new();
+
+    let ast = match parse(&source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            writeln!(out, "--- syntax errors").unwrap();
+            for e in errors {
+                writeln!(out, "{} {}", location(&e.span), e).unwrap();
+            }
+            return out;
+        }
+    };
+
+    let mut options = Options {
+        strict: directive(&source, "strict").is_some(),
+        ..Options::default()
+    };
+
+    if directive(&source, "no-run").is_none() {
+        render_concrete(&mut out, &ast, &options);
+    }
+
+    if let Some(bounds) = directive(&source, "bounds") {
+        options.bounds = match Interval::from_str(bounds) {
+            Ok(Interval::Range(lower, upper)) => (lower, upper),
+            _ => panic!("{}: invalid bounds `{}`", file, bounds),
+        };
+    }
+
+    let (lower, upper) = options.bounds;
+    writeln!(out, "--- interval [{}, {}]", lower, upper).unwrap();
+    render_analysis(&mut out, &analyze::<Interval>(&ast, &options));
+    writeln!(out, "--- constant").unwrap();
+    render_analysis(&mut out, &analyze::<Constant>(&ast, &options));
+
+    out
+}
+
+fn render_concrete(out: &mut String, ast: &Spanned<Statement>, options: &Options) {
+    // the values drawn from `[a, b]` are the same on every run
+    seed_random(0);
+
+    writeln!(out, "--- concrete").unwrap();
+    match evaluate(ast, options) {
+        Ok(Some(execution)) => {
+            for point in execution.points {
+                let states = match point.states.is_empty() {
+                    true => "unreached".to_string(),
+                    _ => point
+                        .states
+                        .iter()
+                        .map(|s| format!("{{{}}}", s.pretty()))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                };
+                let span = point.point.span();
+                writeln!(out, "{} {} => {}", location(&span), point.point, states).unwrap();
+            }
+            writeln!(out, "final {{{}}}", execution.final_state.pretty()).unwrap();
+        }
+        Ok(None) => writeln!(out, "no final state").unwrap(),
+        Err(e) => writeln!(out, "{} error: {}", location(&e.span), e).unwrap(),
+    }
+}
+
+fn render_analysis<T: Domain>(out: &mut String, analysis: &Analysis<T>) {
+    for point in &analysis.points {
+        let span = point.point.span();
+        writeln!(
+            out,
+            "{} {} => {}",
+            location(&span),
+            point.point,
+            point.invariant
+        )
+        .unwrap();
+    }
+    writeln!(out, "exit {}", analysis.exit_state).unwrap();
+
+    for alarm in &analysis.alarms {
+        writeln!(
+            out,
+            "{} {} {}: {}",
+            location(&alarm.span),
+            alarm.severity,
+            alarm.rule,
+            alarm.message
+        )
+        .unwrap();
+    }
+}
+
+fn location(span: &Span) -> String {
+    format!("{}:{}", span.line, span.column)
+}
+
+// the value of `// name: value` or `// name` among the first comments
+fn directive<'a>(source: &'a str, name: &str) -> Option<&'a str> {
+    source
+        .lines()
+        .take_while(|line| line.starts_with("//"))
+        .filter_map(|line| line.trim_start_matches("//").trim().strip_prefix(name))
+        .map(|rest| rest.trim_start_matches(':').trim())
+        .next()
+}
@@ -0,0 +1,414 @@
+// the laws every abstract domain must obey, checked on random values: `lattice_laws!`
+// applies to any lattice, `domain_laws!` to the domains of values, whose arithmetic
+// and refinement are compared with the concrete semantics
+//
+// a new domain only needs a strategy for its values and a line at the bottom
+
+use proptest::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use whilep::abstract_semantics::state::State;
+use whilep::concrete_semantics::denote;
+use whilep::domain::constant::*;
+use whilep::domain::domain::*;
+use whilep::domain::interval::*;
+use whilep::domain::lattice::*;
+use whilep::parser::ast::*;
+use whilep::parser::span::*;
+use whilep::types::integer::*;
+
+const VARIABLES: [&str; 3] = ["x", "y", "z"];
+
+// --- strategies
+
+fn small() -> impl Strategy<Value = i64> {
+    -20i64..=20
+}
+
+fn interval() -> impl Strategy<Value = Interval> {
+    prop_oneof![
+        1 => Just(Interval::Empty),
+        6 => (small(), small(), any::<bool>(), any::<bool>()).prop_map(|(a, b, neginf, posinf)| {
+            let min = match neginf {
+                true => Integer::NegInf,
+                _ => Integer::Value(a.min(b)),
+            };
+            let max = match posinf {
+                true => Integer::PosInf,
+                _ => Integer::Value(a.max(b)),
+            };
+            Interval::Range(min, max)
+        }),
+    ]
+}
+
+fn constant() -> impl Strategy<Value = Constant> {
+    prop_oneof![
+        1 => Just(Constant::None),
+        1 => Just(Constant::Any),
+        4 => small().prop_map(|c| Constant::Value(Integer::Value(c))),
+    ]
+}
+
+// states over a few variables, each one unassigned or bound to a value
+fn state<T: Domain + 'static>(value: impl Strategy<Value = T>) -> impl Strategy<Value = State<T>> {
+    prop_oneof![
+        1 => Just(State::Bottom),
+        6 => prop::collection::vec(prop::option::of(value), VARIABLES.len()).prop_map(|values| {
+            VARIABLES
+                .iter()
+                .zip(values)
+                .fold(State::new(), |state, (var, value)| match value {
+                    Some(value) => state.put(&var.to_string(), value),
+                    None => state,
+                })
+        }),
+    ]
+}
+
+// expressions over `x` and `y` without side effects or random values
+fn aexpr() -> impl Strategy<Value = Spanned<ArithmeticExpr>> {
+    let leaf = prop_oneof![
+        (-5i64..=5).prop_map(|n| ArithmeticExpr::Number(Integer::Value(n))),
+        prop::sample::select(vec!["x", "y"]).prop_map(|v| ArithmeticExpr::Variable(v.to_string())),
+    ]
+    .prop_map(spanned);
+
+    leaf.prop_recursive(3, 12, 2, |inner| {
+        (0..4usize, inner.clone(), inner).prop_map(|(op, a1, a2)| {
+            let (a1, a2) = (Box::new(a1), Box::new(a2));
+            spanned(match op {
+                0 => ArithmeticExpr::Add(a1, a2),
+                1 => ArithmeticExpr::Sub(a1, a2),
+                2 => ArithmeticExpr::Mul(a1, a2),
+                _ => ArithmeticExpr::Div(a1, a2),
+            })
+        })
+    })
+}
+
+fn comparison() -> impl Strategy<Value = Spanned<BooleanExpr>> {
+    (0..6usize, aexpr(), aexpr()).prop_map(|(op, a1, a2)| {
+        let (a1, a2) = (Box::new(a1), Box::new(a2));
+        spanned(match op {
+            0 => BooleanExpr::NumEq(a1, a2),
+            1 => BooleanExpr::NumNotEq(a1, a2),
+            2 => BooleanExpr::NumLt(a1, a2),
+            3 => BooleanExpr::NumGt(a1, a2),
+            4 => BooleanExpr::NumLtEq(a1, a2),
+            _ => BooleanExpr::NumGtEq(a1, a2),
+        })
+    })
+}
+
+fn spanned<T>(node: T) -> Spanned<T> {
+    Spanned::new(node, Span::default())
+}
+
+// --- lattice laws
+
+fn order_laws<T: Lattice + Clone + PartialEq + Debug>(
+    a: &T,
+    b: &T,
+    c: &T,
+) -> Result<(), TestCaseError> {
+    prop_assert!(a.leq(a), "leq is not reflexive");
+    if a.leq(b) && b.leq(a) {
+        prop_assert_eq!(a, b, "leq is not antisymmetric");
+    }
+    if a.leq(b) && b.leq(c) {
+        prop_assert!(a.leq(c), "leq is not transitive");
+    }
+    if a == b {
+        prop_assert!(a.leq(b), "equal values are not ordered");
+    }
+    prop_assert!(T::BOT.leq(a), "BOT is not the least element");
+    prop_assert!(
+        a.is_bottom() == (a == &T::BOT),
+        "is_bottom disagrees with BOT"
+    );
+    Ok(())
+}
+
+fn join_laws<T: Lattice + Clone + PartialEq + Debug>(
+    a: &T,
+    b: &T,
+    c: &T,
+) -> Result<(), TestCaseError> {
+    let ab = a.lub(b);
+    prop_assert_eq!(&ab, &b.lub(a), "lub is not commutative");
+    prop_assert_eq!(ab.lub(c), a.lub(&b.lub(c)), "lub is not associative");
+    prop_assert_eq!(&a.lub(a), a, "lub is not idempotent");
+    prop_assert!(a.leq(&ab) && b.leq(&ab), "lub is not an upper bound");
+    prop_assert_eq!(&a.lub(&T::BOT), a, "BOT is not neutral for lub");
+    if a.leq(c) && b.leq(c) {
+        prop_assert!(ab.leq(c), "lub is not the least upper bound");
+    }
+    prop_assert_eq!(a.leq(b), &ab == b, "lub disagrees with leq");
+    Ok(())
+}
+
+fn meet_laws<T: Lattice + Clone + PartialEq + Debug>(
+    a: &T,
+    b: &T,
+    c: &T,
+) -> Result<(), TestCaseError> {
+    let ab = a.glb(b);
+    prop_assert_eq!(&ab, &b.glb(a), "glb is not commutative");
+    prop_assert_eq!(ab.glb(c), a.glb(&b.glb(c)), "glb is not associative");
+    prop_assert_eq!(&a.glb(a), a, "glb is not idempotent");
+    prop_assert!(ab.leq(a) && ab.leq(b), "glb is not a lower bound");
+    prop_assert!(a.glb(&T::BOT).is_bottom(), "BOT is not absorbing for glb");
+    if c.leq(a) && c.leq(b) {
+        prop_assert!(c.leq(&ab), "glb is not the greatest lower bound");
+    }
+    prop_assert_eq!(&a.lub(&ab), a, "lub does not absorb glb");
+    prop_assert_eq!(&a.glb(&a.lub(b)), a, "glb does not absorb lub");
+    Ok(())
+}
+
+// widening an increasing chain covers it and stabilizes after at most `height`
+// strict increases, narrowing a decreasing step stays between its two ends
+fn widening_laws<T: Lattice + Clone + PartialEq + Debug>(
+    start: &T,
+    chain: &[T],
+    height: usize,
+) -> Result<(), TestCaseError> {
+    let mut x = start.clone();
+    let mut increases = 0;
+
+    for y in chain {
+        let next = x.widen(&x.lub(y));
+        prop_assert!(
+            x.leq(&next) && y.leq(&next),
+            "widening does not cover the chain"
+        );
+        if next != x {
+            increases += 1;
+        }
+        x = next;
+    }
+    prop_assert!(
+        increases <= height,
+        "widening increased {} times, more than {}",
+        increases,
+        height
+    );
+
+    for y in chain {
+        let below = x.glb(y);
+        let narrowed = x.narrow(&below);
+        prop_assert!(
+            below.leq(&narrowed) && narrowed.leq(&x),
+            "narrowing does not stay between its operands"
+        );
+    }
+    Ok(())
+}
+
+macro_rules! lattice_laws {
+    ($name:ident, $strategy:expr, $height:expr) => {
+        mod $name {
+            use super::*;
+
+            proptest! {
+                #[test]
+                fn order(a in $strategy, b in $strategy, c in $strategy) {
+                    order_laws(&a, &b, &c)?;
+                }
+
+                #[test]
+                fn join(a in $strategy, b in $strategy, c in $strategy) {
+                    join_laws(&a, &b, &c)?;
+                }
+
+                #[test]
+                fn meet(a in $strategy, b in $strategy, c in $strategy) {
+                    meet_laws(&a, &b, &c)?;
+                }
+
+                #[test]
+                fn widening(start in $strategy, chain in prop::collection::vec($strategy, 1..20)) {
+                    widening_laws(&start, &chain, $height)?;
+                }
+            }
+        }
+    };
+}
+
+// --- domain laws
+
+// the abstraction of a single number
+fn alpha<T: Domain>(n: i64) -> T {
+    T::eval_aexpr(&ArithmeticExpr::Number(Integer::Value(n)), &State::new()).0
+}
+
+// an operation by its name, on abstract values and on numbers
+type Operation<T> = (&'static str, fn(T, T) -> T, fn(i64, i64) -> Option<i64>);
+
+fn operations<T: Domain>() -> [Operation<T>; 4] {
+    [
+        ("+", |a, b| a + b, |a, b| Some(a + b)),
+        ("-", |a, b| a - b, |a, b| Some(a - b)),
+        ("*", |a, b| a * b, |a, b| Some(a * b)),
+        ("/", |a, b| a / b, |a, b| a.checked_div(b)),
+    ]
+}
+
+fn monotonicity_laws<T: Domain>(a: T, b: T, c: T, d: T) -> Result<(), TestCaseError> {
+    let (a2, b2) = (a.lub(&c), b.lub(&d));
+    for (name, op, _) in operations::<T>() {
+        prop_assert!(
+            op(a, b).leq(&op(a2, b2)),
+            "{} is not monotone: {} {} {} = {} is not below {} {} {} = {}",
+            name,
+            a,
+            name,
+            b,
+            op(a, b),
+            a2,
+            name,
+            b2,
+            op(a2, b2)
+        );
+    }
+    Ok(())
+}
+
+// the result of each operation on numbers of `a` and `b` is in the result on `a`
+// and `b`, a division by zero having no result
+fn arithmetic_soundness<T: Domain>(m: i64, n: i64, a: T, b: T) -> Result<(), TestCaseError> {
+    let (a, b) = (alpha::<T>(m).lub(&a), alpha::<T>(n).lub(&b));
+    for (name, op, concrete) in operations::<T>() {
+        if let Some(result) = concrete(m, n) {
+            prop_assert!(
+                alpha::<T>(result).leq(&op(a, b)),
+                "{} {} {} = {} is not in {} {} {} = {}",
+                m,
+                name,
+                n,
+                result,
+                a,
+                name,
+                b,
+                op(a, b)
+            );
+        }
+    }
+    Ok(())
+}
+
+// every number `[x, y]` may draw is in its value, for `x` and `y` in `a` and `b`
+fn random_soundness<T: Domain>((m, n): (i64, i64), (a, b): (T, T)) -> Result<(), TestCaseError> {
+    let (a, b) = (alpha::<T>(m).lub(&a), alpha::<T>(n).lub(&b));
+    let (x, y) = ("x".to_string(), "y".to_string());
+
+    let state = State::new().put(&x, a).put(&y, b);
+    let expr = ArithmeticExpr::Interval(
+        Box::new(spanned(ArithmeticExpr::Variable(x))),
+        Box::new(spanned(ArithmeticExpr::Variable(y))),
+    );
+    let value = T::eval_aexpr(&expr, &state).0;
+
+    for drawn in m..=n {
+        prop_assert!(
+            alpha::<T>(drawn).leq(&value),
+            "{} may be drawn from [x, y] but is not in {} in {}",
+            drawn,
+            value,
+            state
+        );
+    }
+    Ok(())
+}
+
+// refining a state by a comparison keeps every concrete state where it holds
+fn refine_soundness<T: Domain>(
+    (m, n): (i64, i64),
+    (a, b): (T, T),
+    cond: &Spanned<BooleanExpr>,
+) -> Result<(), TestCaseError> {
+    let (a, b) = (alpha::<T>(m).lub(&a), alpha::<T>(n).lub(&b));
+    let (x, y) = ("x".to_string(), "y".to_string());
+
+    let abstract_state = State::new().put(&x, a).put(&y, b);
+    let concrete_state = HashMap::from([
+        (x.clone(), Integer::Value(m)),
+        (y.clone(), Integer::Value(n)),
+    ]);
+
+    // a division by zero stops the program, no state gets through the condition
+    let Ok((holds, _)) = denote::eval_bexpr(cond, &concrete_state) else {
+        return Ok(());
+    };
+    let cond = match holds {
+        true => cond.node.clone(),
+        _ => cond.node.negate(),
+    };
+
+    let refined = T::eval_bexpr(&cond, &abstract_state);
+    prop_assert!(
+        alpha::<T>(m).leq(&refined.read(&x)) && alpha::<T>(n).leq(&refined.read(&y)),
+        "x = {}, y = {} satisfies {} but is not in {} refined to {}",
+        m,
+        n,
+        cond,
+        abstract_state,
+        refined
+    );
+    Ok(())
+}
+
+macro_rules! domain_laws {
+    ($name:ident, $strategy:expr) => {
+        mod $name {
+            use super::*;
+
+            proptest! {
+                #[test]
+                fn monotone_arithmetic(a in $strategy, b in $strategy, c in $strategy, d in $strategy) {
+                    monotonicity_laws(a, b, c, d)?;
+                }
+
+                #[test]
+                fn sound_arithmetic(m in small(), n in small(), a in $strategy, b in $strategy) {
+                    arithmetic_soundness(m, n, a, b)?;
+                }
+
+                #[test]
+                fn sound_random(m in small(), n in small(), a in $strategy, b in $strategy) {
+                    random_soundness((m, n), (a, b))?;
+                }
+
+                #[test]
+                fn sound_refine(
+                    m in -10i64..=10,
+                    n in -10i64..=10,
+                    a in $strategy,
+                    b in $strategy,
+                    cond in comparison(),
+                ) {
+                    refine_soundness((m, n), (a, b), &cond)?;
+                }
+            }
+        }
+    };
+}
+
+// the height bounds how often widening can increase: the two bounds of an interval
+// go to infinity once each, a constant goes from a value to any
+lattice_laws!(interval_lattice, interval(), 3);
+lattice_laws!(constant_lattice, constant(), 2);
+lattice_laws!(
+    interval_state_lattice,
+    state(interval()),
+    3 * (VARIABLES.len() + 1) + 1
+);
+lattice_laws!(
+    constant_state_lattice,
+    state(constant()),
+    2 * (VARIABLES.len() + 1) + 1
+);
+
+domain_laws!(interval_domain, interval());
+domain_laws!(constant_domain, constant());